@@ -1,25 +1,43 @@
 """Redirect HTTP requests to another server."""
 from mitmproxy import http
+import json
 import logging
 import os
+from typing import Optional
 
 logger = logging.getLogger(__name__)
 
-def proxy_address(flow: http.HTTPFlow) -> tuple[str, int]:
+def env_port(name: str) -> Optional[int]:
+    port = os.getenv(name)
+    return int(port) if port else None
+
+def proxy_port(flow: http.HTTPFlow) -> Optional[int]:
     if flow.request.pretty_host == "lake.nearhat":
-        return ("localhost", int(os.getenv('NEARHAT_LAKE_S3_PORT')))
+        return env_port('NEARHAT_LAKE_S3_PORT')
     elif flow.request.pretty_host == "rpc.nearhat":
-        return ("localhost", int(os.getenv('NEARHAT_RPC_PORT')))
+        return env_port('NEARHAT_RPC_PORT')
     elif flow.request.pretty_host == "relayer.nearhat":
-        return ("localhost", int(os.getenv('NEARHAT_RELAYER_PORT')))
+        return env_port('NEARHAT_RELAYER_PORT')
     elif flow.request.pretty_host == "explorer.nearhat":
-        return ("localhost", int(os.getenv('NEARHAT_EXPLORER_UI_PORT')))
+        return env_port('NEARHAT_EXPLORER_UI_PORT')
     elif flow.request.pretty_host == "playground.nearhat":
-        return ("localhost", int(os.getenv('NEARHAT_GRAPHQL_PLAYGROUND_PORT')))
+        return env_port('NEARHAT_GRAPHQL_PLAYGROUND_PORT')
+    elif flow.request.pretty_host.startswith("relayer-") and flow.request.pretty_host.endswith(".nearhat"):
+        name = flow.request.pretty_host[len("relayer-"):-len(".nearhat")]
+        ports = json.loads(os.getenv('NEARHAT_RELAYER_INSTANCE_PORTS', '{}'))
+        return int(ports[name]) if name in ports else None
     else:
-        return ("localhost", 3000)
+        return 3000
 
 def request(flow: http.HTTPFlow) -> None:
-    address = proxy_address(flow)
-    flow.request.host = address[0]
-    flow.request.port = address[1]
\ No newline at end of file
+    port = proxy_port(flow)
+    if port is None:
+        # The service is not part of the running environment, e.g. a disabled explorer.
+        flow.response = http.Response.make(
+            503,
+            f"{flow.request.pretty_host} is not running in this NEARHat environment\n",
+            {"Content-Type": "text/plain"},
+        )
+        return
+    flow.request.host = "localhost"
+    flow.request.port = port
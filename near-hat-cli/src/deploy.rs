@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Args;
+use near_primitives::types::AccountId;
+use near_workspaces::types::NearToken;
+
+use crate::ConnectArgs;
+
+#[derive(Args, Debug)]
+pub struct DeployArgs {
+    #[command(flatten)]
+    connect: ConnectArgs,
+    /// Account to deploy to. Reused if its key is in the key file, otherwise created under the root account.
+    account_id: AccountId,
+    /// Contract wasm file, or a near-sdk crate (`Cargo.toml` or its directory) to build first.
+    artifact: PathBuf,
+    /// Method to call after deployment.
+    #[arg(long)]
+    init_method: Option<String>,
+    /// JSON arguments for the init method.
+    #[arg(long, default_value = "{}", requires = "init_method")]
+    init_args: String,
+    /// Initial balance in NEAR when the account has to be created.
+    #[arg(long, default_value_t = 10)]
+    initial_balance: u128,
+}
+
+impl DeployArgs {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let wasm = if self.artifact.extension().map_or(false, |ext| ext == "wasm") {
+            std::fs::read(&self.artifact)
+                .with_context(|| format!("reading wasm file {}", self.artifact.display()))?
+        } else {
+            near_hat::build_contract(&self.artifact)?
+        };
+        let init_args: serde_json::Value =
+            serde_json::from_str(&self.init_args).context("parsing init args")?;
+
+        let nearcore_ctx = crate::connect(&self.connect).await?;
+        let keys = crate::read_key_file(&self.connect.keys_file)?;
+        let secret_key = match keys[self.account_id.as_str()].as_str() {
+            Some(secret_key) => Some(secret_key.parse()?),
+            None => None,
+        };
+        let account = nearcore_ctx
+            .get_or_create_account(
+                &self.account_id,
+                secret_key,
+                NearToken::from_near(self.initial_balance),
+            )
+            .await?;
+        crate::save_key(&self.connect.keys_file, account.id(), account.secret_key())?;
+
+        let init = self
+            .init_method
+            .as_deref()
+            .map(|method| (method, init_args));
+        let contract = nearcore_ctx.deploy_contract(&account, &wasm, init).await?;
+        println!("Deployed {} bytes to {}", wasm.len(), contract.id());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,231 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use clap::{Args, Subcommand};
+use near_hat::{HasuraClient, IndexerLogCursor, IndexerRegistry};
+use near_primitives::types::AccountId;
+use serde_json::Value;
+
+use crate::ConnectArgs;
+
+#[derive(Args, Debug)]
+pub struct RegistryArgs {
+    #[command(flatten)]
+    connect: ConnectArgs,
+    /// QueryAPI registry contract.
+    #[arg(long, default_value = "dev-queryapi.test.near")]
+    registry_id: AccountId,
+    /// Account that owns the indexers, its key is looked up in the key file.
+    #[arg(long, default_value = "dev-queryapi.test.near")]
+    account_id: AccountId,
+}
+
+#[derive(Args, Debug)]
+pub struct IndexerSourceArgs {
+    /// JavaScript file with the indexer code.
+    #[arg(long)]
+    code: PathBuf,
+    /// SQL file with the indexer schema.
+    #[arg(long)]
+    schema: PathBuf,
+    /// Index successful actions on this account.
+    #[arg(long, conflicts_with = "filter", required_unless_present = "filter")]
+    affected_account_id: Option<String>,
+    /// Raw indexer rule as JSON.
+    #[arg(long)]
+    filter: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum IndexerCommand {
+    /// Register (or update) an indexer function.
+    Register {
+        #[command(flatten)]
+        registry: RegistryArgs,
+        name: String,
+        #[command(flatten)]
+        source: IndexerSourceArgs,
+        /// Block height to start indexing from, defaults to the chain head.
+        #[arg(long)]
+        start_block: Option<u64>,
+        /// Redis used by the coordinator (printed by `start`). When set, wait until the
+        /// coordinator picks up the indexer.
+        #[arg(long)]
+        redis_address: Option<String>,
+        /// Seconds to wait for the coordinator.
+        #[arg(long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// Re-register an indexer whenever its code or schema changes and stream its logs.
+    Dev {
+        #[command(flatten)]
+        registry: RegistryArgs,
+        name: String,
+        #[command(flatten)]
+        source: IndexerSourceArgs,
+        /// Block height to re-run the indexer from after every change, defaults to the chain head.
+        #[arg(long)]
+        from_block: Option<u64>,
+        /// Hasura instance of the running environment.
+        #[arg(long, default_value = "http://playground.nearhat")]
+        hasura_url: String,
+        #[arg(long, default_value = "nearhat")]
+        hasura_admin_secret: String,
+    },
+    /// List registered indexers.
+    List {
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+    /// Remove an indexer function.
+    Remove {
+        #[command(flatten)]
+        registry: RegistryArgs,
+        name: String,
+    },
+}
+
+impl RegistryArgs {
+    async fn registry(&self) -> anyhow::Result<IndexerRegistry> {
+        let nearcore_ctx = crate::connect(&self.connect).await?;
+        let keys = crate::read_key_file(&self.connect.keys_file)?;
+        let secret_key = keys[self.account_id.as_str()]
+            .as_str()
+            .with_context(|| {
+                format!("no key for {} in {}", self.account_id, self.connect.keys_file.display())
+            })?
+            .parse()?;
+        let account = nearcore_ctx.account(self.account_id.clone(), secret_key);
+        Ok(IndexerRegistry::new(account, self.registry_id.clone()))
+    }
+}
+
+impl IndexerSourceArgs {
+    /// Read the code, schema and filter.
+    fn load(&self) -> anyhow::Result<(String, String, Value)> {
+        let code = std::fs::read_to_string(&self.code)
+            .with_context(|| format!("reading indexer code {}", self.code.display()))?;
+        let schema = std::fs::read_to_string(&self.schema)
+            .with_context(|| format!("reading indexer schema {}", self.schema.display()))?;
+        let filter = match (&self.filter, &self.affected_account_id) {
+            (Some(filter), _) => serde_json::from_str(filter).context("parsing filter")?,
+            (None, Some(affected_account_id)) => near_hat::action_filter(affected_account_id),
+            (None, None) => unreachable!("clap requires a filter or an affected account"),
+        };
+        Ok((code, schema, filter))
+    }
+
+    fn modified(&self) -> anyhow::Result<[SystemTime; 2]> {
+        Ok([
+            std::fs::metadata(&self.code)?.modified()?,
+            std::fs::metadata(&self.schema)?.modified()?,
+        ])
+    }
+}
+
+impl IndexerCommand {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            IndexerCommand::Register {
+                registry,
+                name,
+                source,
+                start_block,
+                redis_address,
+                timeout,
+            } => {
+                let (code, schema, filter) = source.load()?;
+                let registry = registry.registry().await?;
+                registry
+                    .register(&name, &code, &schema, &filter, start_block)
+                    .await?;
+                println!("Registered indexer {}/{}", registry.account.id(), name);
+                if let Some(redis_address) = redis_address {
+                    registry
+                        .wait_for_coordinator(&redis_address, &name, Duration::from_secs(timeout))
+                        .await?;
+                    println!("Indexer picked up by the coordinator");
+                }
+            }
+            IndexerCommand::Dev {
+                registry,
+                name,
+                source,
+                from_block,
+                hasura_url,
+                hasura_admin_secret,
+            } => {
+                let registry = registry.registry().await?;
+                let hasura = HasuraClient::new(&hasura_url, &hasura_admin_secret);
+                let function_name = format!("{}/{}", registry.account.id(), name);
+                let mut last_modified = None;
+                let mut log_cursor = IndexerLogCursor::default();
+                println!("Watching {} and {}", source.code.display(), source.schema.display());
+                // Errors talking to the node or Hasura are transient while the environment
+                // (re)starts, so they are printed and retried on the next tick.
+                loop {
+                    match source.modified() {
+                        Ok(modified) if last_modified != Some(modified) => match source.load() {
+                            Ok(loaded) => match reregister(&registry, &hasura, &name, &loaded, from_block).await {
+                                Ok(()) => {
+                                    last_modified = Some(modified);
+                                    log_cursor = IndexerLogCursor::default();
+                                    println!("Re-registered {function_name}");
+                                }
+                                Err(err) => eprintln!("Re-registering {function_name} failed, retrying: {err:#}"),
+                            },
+                            // Keep watching, the file may be mid-edit.
+                            Err(err) => {
+                                last_modified = Some(modified);
+                                eprintln!("Skipping reload: {err:#}");
+                            }
+                        },
+                        Ok(_) => {}
+                        Err(err) => eprintln!("Checking {} for changes failed: {err:#}", source.code.display()),
+                    }
+
+                    match hasura
+                        .indexer_logs(&function_name, &mut log_cursor)
+                        .await
+                    {
+                        Ok(entries) => {
+                            for entry in entries {
+                                println!(
+                                    "[{}] {}",
+                                    entry.block_height,
+                                    entry.message.as_deref().unwrap_or_default()
+                                );
+                            }
+                        }
+                        Err(err) => eprintln!("Fetching logs of {function_name} failed: {err:#}"),
+                    }
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                }
+            }
+            IndexerCommand::List { registry } => {
+                let indexers = registry.registry().await?.list().await?;
+                println!("{}", serde_json::to_string_pretty(&indexers)?);
+            }
+            IndexerCommand::Remove { registry, name } => {
+                let registry = registry.registry().await?;
+                registry.remove(&name).await?;
+                println!("Removed indexer {}/{}", registry.account.id(), name);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Drop the indexer's data and register it again with new code, schema and filter.
+async fn reregister(
+    registry: &IndexerRegistry,
+    hasura: &HasuraClient,
+    name: &str,
+    (code, schema, filter): &(String, String, Value),
+    from_block: Option<u64>,
+) -> anyhow::Result<()> {
+    hasura.reset_indexer(registry.account.id().as_str(), name).await?;
+    registry.register(name, code, schema, filter, from_block).await
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use clap::{Args, Subcommand};
+use near_hat::LakeBucket;
+
+use crate::ConnectArgs;
+
+/// Lake bucket of a running environment.
+#[derive(Args, Debug)]
+pub struct BucketArgs {
+    /// S3 endpoint of the running environment.
+    #[arg(long, default_value = "http://lake.nearhat")]
+    s3_url: String,
+    #[arg(long, default_value = "localnet")]
+    bucket: String,
+}
+
+impl BucketArgs {
+    pub fn bucket(&self) -> LakeBucket {
+        LakeBucket::new(&self.s3_url, &self.bucket)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LakeCommand {
+    /// Download blocks into a directory that can be loaded with `lake-only --fixtures`.
+    Export {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        #[arg(long)]
+        from: u64,
+        #[arg(long)]
+        to: u64,
+        dir: PathBuf,
+    },
+    /// Upload a directory written by `lake export`.
+    Import {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        dir: PathBuf,
+    },
+    /// List the heights of uploaded blocks.
+    Ls {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        #[arg(long)]
+        from: Option<u64>,
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Pretty-print the files of a block, or only one of them (e.g. `block.json`, `shard_0.json`).
+    Get {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        height: u64,
+        #[arg(long)]
+        file: Option<String>,
+    },
+    /// Show the latest uploaded height and the node's head.
+    Latest {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        #[command(flatten)]
+        connect: ConnectArgs,
+    },
+}
+
+impl LakeCommand {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            LakeCommand::Export {
+                bucket,
+                from,
+                to,
+                dir,
+            } => {
+                let blocks = bucket.bucket().export(from, to, &dir).await?;
+                println!("Exported {} blocks to {}", blocks, dir.display());
+            }
+            LakeCommand::Import { bucket, dir } => {
+                let blocks = bucket.bucket().import(&dir).await?;
+                println!("Imported {} blocks from {}", blocks, dir.display());
+            }
+            LakeCommand::Ls { bucket, from, to } => {
+                for height in bucket.bucket().heights(from, to).await? {
+                    println!("{}", height);
+                }
+            }
+            LakeCommand::Get {
+                bucket,
+                height,
+                file,
+            } => {
+                let bucket = bucket.bucket();
+                let files = match file {
+                    Some(file) => vec![file],
+                    None => bucket.files(height).await?,
+                };
+                anyhow::ensure!(!files.is_empty(), "block {} is not in the bucket", height);
+                for file in files {
+                    let contents = bucket.get_json(height, &file).await?;
+                    println!("==> {}/{} <==", height, file);
+                    println!("{}", serde_json::to_string_pretty(&contents)?);
+                }
+            }
+            LakeCommand::Latest { bucket, connect } => {
+                let latest = bucket.bucket().latest_height().await?;
+                match latest {
+                    Some(height) => println!("Latest uploaded block: {}", height),
+                    None => println!("Latest uploaded block: none"),
+                }
+                // `lake-only` environments have no node.
+                match crate::connect(&connect).await {
+                    Ok(nearcore_ctx) => {
+                        let head = nearcore_ctx.worker.view_block().await?.height();
+                        println!("Node head: {}", head);
+                        if let Some(height) = latest {
+                            println!("Lake is {} blocks behind", head.saturating_sub(height));
+                        }
+                    }
+                    Err(err) => println!("Node head: unavailable ({err:#})"),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
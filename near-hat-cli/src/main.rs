@@ -1,29 +1,136 @@
+mod deploy;
+mod indexer;
+mod lake;
+mod open;
+mod patch;
+mod state;
+mod tx;
+
 use std::cell::RefCell;
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
-use clap::Parser;
-use near_hat::{DockerClient, NearHat};
+use anyhow::Context;
+use clap::{Args, Parser};
+use near_hat::{DockerClient, ExplorerConfig, LakeFinality, LakeIndexerOptions, LakeStorageKind, NearHat, NearHatConfig, NearHatLake, NearHatSandbox, NearcoreCtx, RelayerInstanceConfig, RelayerPolicy, StandardContract, SyncMode};
 use near_primitives::account::AccessKey;
 use near_primitives::types::AccountId;
-use near_workspaces::types::{PublicKey, KeyType};
+use near_workspaces::types::{PublicKey, KeyType, NearToken};
 use near_workspaces::{network::Sandbox, types::SecretKey};
 use near_workspaces::{Worker, Contract};
 use tokio::io::{stdin, AsyncReadExt};
 use tracing_subscriber::EnvFilter;
 use serde_json::{json, Value};
+use deploy::DeployArgs;
+use indexer::IndexerCommand;
+use lake::LakeCommand;
+use open::OpenArgs;
+use patch::PatchCommand;
+use state::StateCommand;
+use tx::TxCommand;
 extern crate ctrlc;
 
+const KEY_FILE_PATH: &str = "tests/data/keys.json";
+
+/// Connection to an already running NEARHat environment.
+#[derive(Args, Debug)]
+pub struct ConnectArgs {
+    /// RPC address of the running environment.
+    #[arg(long, default_value = "http://rpc.nearhat")]
+    rpc_url: String,
+    /// Key file exported by the running environment.
+    #[arg(long, default_value = KEY_FILE_PATH)]
+    keys_file: PathBuf,
+    /// Validator account of the running environment, its key is looked up in the key file.
+    #[arg(long, default_value = "test.near")]
+    validator_account_id: AccountId,
+}
+
 #[derive(Parser, Debug)]
 pub enum Cli {
     Start {
         /// Contracts to spoon from mainnet.
         #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
         contracts_to_spoon: Vec<AccountId>,
+        /// Standard contracts to pre-deploy from vendored wasm (linkdrop, social-db, ft, nft, multisig, wrap-near).
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        standard_contracts: Vec<StandardContract>,
+        /// Contracts the relayer pays for, in addition to the creator account.
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        relayer_whitelisted_contracts: Vec<AccountId>,
+        /// Delegate action receivers the relayer accepts, in addition to the creator account.
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        relayer_whitelisted_receivers: Vec<AccountId>,
+        /// Deploy a local social DB and enable relayer shared storage on it.
+        #[arg(long)]
+        relayer_social_db: bool,
+        /// Additional named relayers, each with its own account, served at `relayer-<name>.nearhat`.
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        relayer_instances: Vec<String>,
+        /// S3 server for the Lake bucket (localstack, minio).
+        #[arg(long, default_value_t = LakeStorageKind::LocalStack)]
+        lake_storage: LakeStorageKind,
+        /// Where the Lake indexer starts streaming from (latest, interruption, block:<height>).
+        #[arg(long, default_value_t = SyncMode::FromLatest)]
+        lake_sync_mode: SyncMode,
+        /// Number of blocks the Lake indexer uploads concurrently.
+        #[arg(long)]
+        lake_concurrency: Option<u16>,
+        /// Finality of the blocks the Lake indexer streams (optimistic, near-final, final).
+        #[arg(long)]
+        lake_finality: Option<LakeFinality>,
+        /// Do not start the explorer.
+        #[arg(long)]
+        no_explorer: bool,
+        /// Only index into the explorer database, without starting its backend and frontend.
+        #[arg(long)]
+        no_explorer_ui: bool,
     },
+    /// Start only a nearcore sandbox node (no Lake, Relayer, QueryAPI or Explorer).
+    Sandbox {
+        /// Contracts to spoon from mainnet.
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        contracts_to_spoon: Vec<AccountId>,
+        /// Standard contracts to pre-deploy from vendored wasm (linkdrop, social-db, ft, nft, multisig, wrap-near).
+        #[arg(long, value_parser, num_args = 1.., value_delimiter = ',')]
+        standard_contracts: Vec<StandardContract>,
+    },
+    /// Start only a Lake bucket, optionally loaded with blocks from `lake export`.
+    LakeOnly {
+        /// Directory written by `lake export`.
+        #[arg(long)]
+        fixtures: Option<PathBuf>,
+        /// S3 server for the Lake bucket (localstack, minio).
+        #[arg(long, default_value_t = LakeStorageKind::LocalStack)]
+        lake_storage: LakeStorageKind,
+    },
+    /// Patch state of a running environment.
+    Patch {
+        #[command(flatten)]
+        connect: ConnectArgs,
+        #[command(subcommand)]
+        command: PatchCommand,
+    },
+    /// Build (optionally) and deploy a contract to a running environment.
+    Deploy(DeployArgs),
+    /// Dump and diff account state.
+    #[command(subcommand)]
+    State(StateCommand),
+    /// Register, list and remove QueryAPI indexers.
+    #[command(subcommand)]
+    Indexer(IndexerCommand),
+    /// Inspect, export and import Lake bucket contents.
+    #[command(subcommand)]
+    Lake(LakeCommand),
+    /// Inspect transactions using the Lake bucket.
+    #[command(subcommand)]
+    Tx(TxCommand),
+    /// Open a transaction, account or block in the explorer.
+    Open(OpenArgs),
 }
 
 async fn patch_existing_account(worker: &Worker<Sandbox>, account_id: &AccountId, key_json_ref: Rc<RefCell<Value>>) -> anyhow::Result<()> {
@@ -63,6 +170,54 @@ async fn spoon_contracts(worker: &Worker<Sandbox>, contracts: &[AccountId], key_
     Ok(())
 }
 
+fn write_key_file(key_json_ref: Rc<RefCell<Value>>) -> anyhow::Result<()> {
+    let key_file = &mut File::create(KEY_FILE_PATH)?;
+    let json_str = serde_json::to_string(&*key_json_ref.borrow())?;
+    key_file.write_all(json_str.as_bytes())?;
+    Ok(())
+}
+
+fn read_key_file(path: &Path) -> anyhow::Result<Value> {
+    let json_str = std::fs::read_to_string(path)
+        .with_context(|| format!("reading key file {}", path.display()))?;
+    Ok(serde_json::from_str(&json_str)?)
+}
+
+fn save_key(path: &Path, account_id: &AccountId, secret_key: &SecretKey) -> anyhow::Result<()> {
+    let mut keys = read_key_file(path)?;
+    keys[account_id.to_string()] = json!(secret_key.to_string());
+    std::fs::write(path, serde_json::to_string(&keys)?)
+        .with_context(|| format!("writing key file {}", path.display()))?;
+    Ok(())
+}
+
+async fn connect(args: &ConnectArgs) -> anyhow::Result<NearcoreCtx> {
+    let keys = read_key_file(&args.keys_file)?;
+    let validator_sk = keys[args.validator_account_id.as_str()]
+        .as_str()
+        .with_context(|| format!("no key for {} in {}", args.validator_account_id, args.keys_file.display()))?
+        .parse()?;
+    NearcoreCtx::connect(&args.rpc_url, args.validator_account_id.clone(), validator_sk).await
+}
+
+async fn wait_for_exit() -> anyhow::Result<()> {
+    println!("\nPress any button to exit and destroy all containers...");
+
+    // Create a mutable flag to indicate if CTRL+C was received
+    let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+    let r = running.clone();
+
+    // Set up a CTRL+C handler
+    ctrlc::set_handler(move || {
+        r.store(false, std::sync::atomic::Ordering::SeqCst);
+    }).expect("Error setting Ctrl-C handler");
+
+    while stdin().read(&mut [0]).await? == 0 && running.load(std::sync::atomic::Ordering::SeqCst) {
+        tokio::time::sleep(std::time::Duration::from_millis(25)).await;
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Install global collector configured based on RUST_LOG env var.
@@ -72,10 +227,52 @@ async fn main() -> anyhow::Result<()> {
     subscriber.init();
 
     match Cli::parse() {
-        Cli::Start { contracts_to_spoon } => {
+        Cli::Start {
+            contracts_to_spoon,
+            standard_contracts,
+            relayer_whitelisted_contracts,
+            relayer_whitelisted_receivers,
+            relayer_social_db,
+            relayer_instances,
+            lake_storage,
+            lake_sync_mode,
+            lake_concurrency,
+            lake_finality,
+            no_explorer,
+            no_explorer_ui,
+        } => {
             let key_json_ref = Rc::new(RefCell::new(json!({})));
             let docker_client = DockerClient::default();
-            let mut near_hat = NearHat::new(&docker_client, "nearhat", key_json_ref.clone()).await?;
+            let config = NearHatConfig {
+                standard_contracts,
+                relayer: RelayerPolicy {
+                    whitelisted_contracts: relayer_whitelisted_contracts,
+                    whitelisted_delegate_action_receiver_ids: relayer_whitelisted_receivers,
+                    ..Default::default()
+                },
+                relayer_social_db,
+                relayer_instances: relayer_instances
+                    .into_iter()
+                    .map(|name| RelayerInstanceConfig {
+                        name,
+                        initial_balance: NearToken::from_near(1000),
+                        policy: RelayerPolicy::default(),
+                    })
+                    .collect(),
+                lake_storage,
+                lake_indexer: LakeIndexerOptions {
+                    sync_mode: lake_sync_mode,
+                    concurrency: lake_concurrency,
+                    finality: lake_finality,
+                    ..Default::default()
+                },
+                explorer: ExplorerConfig {
+                    enabled: !no_explorer,
+                    ui: !no_explorer_ui,
+                    ..Default::default()
+                },
+            };
+            let mut near_hat = NearHat::new(&docker_client, "nearhat", key_json_ref.clone(), &config).await?;
             spoon_contracts(
                 &near_hat.nearhat.lake_indexer_ctx.worker,
                 &contracts_to_spoon,
@@ -83,10 +280,7 @@ async fn main() -> anyhow::Result<()> {
             )
             .await?;
 
-            let key_file_path = "tests/data/keys.json";
-            let key_file = &mut File::create(key_file_path)?;
-            let json_str = serde_json::to_string(&*key_json_ref.borrow())?;
-            key_file.write_all(json_str.as_bytes())?;
+            write_key_file(key_json_ref)?;
 
             println!("\nNEARHat environment is ready:");
             println!(
@@ -106,6 +300,16 @@ async fn main() -> anyhow::Result<()> {
                     .host_http_address_ipv4(),
                 near_hat.nearhat.relayer_ctx.creator_account.id()
             );
+            for instance in &near_hat.nearhat.relayer_ctx.instances {
+                println!(
+                    "  Relayer {}: http://relayer-{}.nearhat ({}), Relayer Account: {}, Redis DB: {}",
+                    instance.name,
+                    instance.name,
+                    instance.relayer.host_http_address_ipv4(),
+                    instance.relayer_account.id(),
+                    instance.redis_db
+                );
+            }
             println!(
                 "  Relayer Redis: {}",
                 near_hat.nearhat.relayer_ctx.redis.host_redis_connection_ipv4()
@@ -139,54 +343,97 @@ async fn main() -> anyhow::Result<()> {
                     .hasura_graphql
                     .hasura_password()
             );
-            println!(
-                "  Explorer Database: {}",
-                near_hat
-                    .nearhat
-                    .explorer_ctx
-                    .database
-                    .host_postgres_connection_string()
-            );
             println!(
                 "  NEAR Lake S3: URL=http://lake.nearhat ({}), Region: {}, Bucket: {}",
                 near_hat
                     .nearhat
                     .lake_indexer_ctx
-                    .localstack
+                    .storage
                     .host_s3_address_ipv4(),
-                near_hat.nearhat.lake_indexer_ctx.localstack.s3_region,
-                near_hat.nearhat.lake_indexer_ctx.localstack.s3_bucket
-            );
-            println!(
-                "  Run `aws --endpoint-url=http://lake.nearhat s3 ls {}/000000000001/` to access block data",
-                near_hat.nearhat.lake_indexer_ctx.localstack.s3_bucket
-            );
-            println!(
-                "  Explorer Backend: {}",
-                near_hat.nearhat.explorer_ctx.backend.host_address_ipv4()
-            );
-            println!(
-                "  Explorer Frontend: http://explorer.nearhat ({})",
-                near_hat.nearhat.explorer_ctx.frontend.host_address_ipv4()
+                near_hat.nearhat.lake_indexer_ctx.storage.s3_region(),
+                near_hat.nearhat.lake_indexer_ctx.storage.s3_bucket()
             );
+            println!("  Run `near-hat-cli lake ls`, `lake get <height>` or `lake latest` to inspect block data");
+            if let Some(explorer_ctx) = &near_hat.nearhat.explorer_ctx {
+                println!(
+                    "  Explorer Database: {}",
+                    explorer_ctx.database.host_postgres_connection_string()
+                );
+                if let Some(backend) = &explorer_ctx.backend {
+                    println!("  Explorer Backend: {}", backend.host_address_ipv4());
+                }
+                if let Some(frontend) = &explorer_ctx.frontend {
+                    println!(
+                        "  Explorer Frontend: http://explorer.nearhat ({})",
+                        frontend.host_address_ipv4()
+                    );
+                }
+            }
+
+            wait_for_exit().await?;
+            println!("\nTerminating all Docker containers and reverse proxy...");
+            let _ = near_hat.reverse_proxy_process.kill();
+        }
+        Cli::Sandbox { contracts_to_spoon, standard_contracts } => {
+            let key_json_ref = Rc::new(RefCell::new(json!({})));
+            let docker_client = DockerClient::default();
+            let config = NearHatConfig {
+                standard_contracts,
+                ..Default::default()
+            };
+            let mut near_hat = NearHatSandbox::new(&docker_client, "nearhat", key_json_ref.clone(), &config).await?;
+            spoon_contracts(
+                &near_hat.nearhat.sandbox_ctx.worker,
+                &contracts_to_spoon,
+                key_json_ref.clone()
+            )
+            .await?;
 
-            println!("\nPress any button to exit and destroy all containers...");
+            write_key_file(key_json_ref)?;
 
-            // Create a mutable flag to indicate if CTRL+C was received
-            let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
-            let r = running.clone();
+            println!("\nNEARHat sandbox is ready:");
+            println!(
+                "  RPC: http://rpc.nearhat ({})",
+                near_hat.nearhat.sandbox_ctx.sandbox.host_rpc_address_ipv4()
+            );
 
-            // Set up a CTRL+C handler
-            ctrlc::set_handler(move || {
-                r.store(false, std::sync::atomic::Ordering::SeqCst);
-            }).expect("Error setting Ctrl-C handler");
+            wait_for_exit().await?;
+            println!("\nTerminating sandbox container and reverse proxy...");
+            let _ = near_hat.reverse_proxy_process.kill();
+        }
+        Cli::LakeOnly { fixtures, lake_storage } => {
+            let docker_client = DockerClient::default();
+            let mut near_hat =
+                NearHatLake::new(&docker_client, "nearhat", lake_storage, fixtures.as_deref()).await?;
 
-            while stdin().read(&mut [0]).await? == 0 && running.load(std::sync::atomic::Ordering::SeqCst) {
-                tokio::time::sleep(std::time::Duration::from_millis(25)).await;
+            println!("\nNEARHat Lake is ready:");
+            println!(
+                "  NEAR Lake S3: URL=http://lake.nearhat ({}), Region: {}, Bucket: {}",
+                near_hat.nearhat.storage.host_s3_address_ipv4(),
+                near_hat.nearhat.storage.s3_region(),
+                near_hat.nearhat.storage.s3_bucket()
+            );
+            if let Some(latest_height) = near_hat.nearhat.bucket().latest_height().await? {
+                println!("  Latest block: {latest_height}");
             }
-            println!("\nTerminating all Docker containers and reverse proxy...");
+
+            wait_for_exit().await?;
+            println!("\nTerminating {} container and reverse proxy...", lake_storage);
             let _ = near_hat.reverse_proxy_process.kill();
         }
+        Cli::Patch { connect: connect_args, command } => {
+            let nearcore_ctx = connect(&connect_args).await?;
+            if let Some((account_id, secret_key)) = command.run(&nearcore_ctx).await? {
+                save_key(&connect_args.keys_file, &account_id, &secret_key)?;
+                println!("Added access key for {account_id}: {secret_key}");
+            }
+        }
+        Cli::Deploy(args) => args.run().await?,
+        Cli::State(command) => command.run().await?,
+        Cli::Indexer(command) => command.run().await?,
+        Cli::Lake(command) => command.run().await?,
+        Cli::Tx(command) => command.run().await?,
+        Cli::Open(args) => args.run()?,
     }
 
     Ok(())
@@ -0,0 +1,45 @@
+use std::process::Command;
+
+use clap::{Args, Subcommand};
+use near_hat::{ExplorerLinks, EXPLORER_PROXY_URL};
+use near_primitives::types::AccountId;
+
+#[derive(Subcommand, Debug)]
+pub enum OpenTarget {
+    Tx { hash: String },
+    Account { account_id: AccountId },
+    Block { height: u64 },
+}
+
+#[derive(Args, Debug)]
+pub struct OpenArgs {
+    #[command(subcommand)]
+    target: OpenTarget,
+    /// Explorer frontend of the running environment.
+    #[arg(long, global = true, default_value = EXPLORER_PROXY_URL)]
+    explorer_url: String,
+    /// Only print the link instead of opening a browser.
+    #[arg(long, global = true)]
+    print: bool,
+}
+
+impl OpenArgs {
+    pub fn run(self) -> anyhow::Result<()> {
+        let links = ExplorerLinks::new(&self.explorer_url);
+        let url = match &self.target {
+            OpenTarget::Tx { hash } => links.tx_url(hash),
+            OpenTarget::Account { account_id } => links.account_url(account_id),
+            OpenTarget::Block { height } => links.block_url(*height),
+        };
+        println!("{}", url);
+        if self.print {
+            return Ok(());
+        }
+
+        let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+        if let Err(err) = Command::new(opener).arg(&url).spawn() {
+            eprintln!("Could not run {opener}, open the link manually: {err}");
+        }
+        Ok(())
+    }
+}
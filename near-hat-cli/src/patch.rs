@@ -0,0 +1,161 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use base64::Engine;
+use clap::{Subcommand, ValueEnum};
+use near_hat::NearcoreCtx;
+use near_primitives::types::AccountId;
+use near_workspaces::types::{KeyType, NearToken, PublicKey, SecretKey};
+use near_workspaces::AccessKey;
+
+/// How a storage key or value passed on the command line is encoded.
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum Encoding {
+    /// Raw UTF-8 string.
+    #[default]
+    Utf8,
+    /// Base64-encoded bytes (e.g. a borsh-serialized value).
+    Base64,
+    /// JSON value, stored in its compact serialized form.
+    Json,
+}
+
+impl Encoding {
+    fn decode(self, input: &str) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Encoding::Utf8 => Ok(input.as_bytes().to_vec()),
+            Encoding::Base64 => base64::engine::general_purpose::STANDARD
+                .decode(input)
+                .context("decoding base64 input"),
+            Encoding::Json => {
+                let value: serde_json::Value =
+                    serde_json::from_str(input).context("parsing JSON input")?;
+                Ok(serde_json::to_vec(&value)?)
+            }
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PatchCommand {
+    /// Set a contract storage key.
+    SetState {
+        account_id: AccountId,
+        key: String,
+        /// Value to store. Use `--value-file` instead to read raw (e.g. borsh) bytes from a file.
+        #[arg(required_unless_present = "value_file")]
+        value: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        key_encoding: Encoding,
+        #[arg(long, value_enum, default_value_t)]
+        value_encoding: Encoding,
+        #[arg(long, conflicts_with = "value")]
+        value_file: Option<PathBuf>,
+    },
+    /// Delete contract storage keys.
+    DeleteState {
+        account_id: AccountId,
+        #[arg(required = true)]
+        keys: Vec<String>,
+        #[arg(long, value_enum, default_value_t)]
+        key_encoding: Encoding,
+    },
+    /// Replace contract code with the given wasm file.
+    Code {
+        account_id: AccountId,
+        wasm_file: PathBuf,
+    },
+    /// Overwrite account balance.
+    Balance {
+        account_id: AccountId,
+        /// New balance in NEAR.
+        #[arg(long, required_unless_present = "yoctonear", conflicts_with = "yoctonear")]
+        near: Option<u128>,
+        /// New balance in yoctoNEAR.
+        #[arg(long)]
+        yoctonear: Option<u128>,
+    },
+    /// Add a full access key. A new key pair is generated if no public key is given.
+    AccessKey {
+        account_id: AccountId,
+        #[arg(long)]
+        public_key: Option<PublicKey>,
+    },
+}
+
+impl PatchCommand {
+    /// Apply the patch; returns a newly generated secret key for the `access-key` command.
+    pub async fn run(self, nearcore_ctx: &NearcoreCtx) -> anyhow::Result<Option<(AccountId, SecretKey)>> {
+        match self {
+            PatchCommand::SetState {
+                account_id,
+                key,
+                value,
+                key_encoding,
+                value_encoding,
+                value_file,
+            } => {
+                let key = key_encoding.decode(&key)?;
+                let value = match (value, value_file) {
+                    (_, Some(path)) => std::fs::read(&path)
+                        .with_context(|| format!("reading value file {}", path.display()))?,
+                    (Some(value), None) => value_encoding.decode(&value)?,
+                    (None, None) => unreachable!("enforced by clap"),
+                };
+                nearcore_ctx.patch_state(&account_id, &key, &value).await?;
+            }
+            PatchCommand::DeleteState {
+                account_id,
+                keys,
+                key_encoding,
+            } => {
+                let keys = keys
+                    .iter()
+                    .map(|key| key_encoding.decode(key))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                nearcore_ctx.delete_state(&account_id, &keys).await?;
+            }
+            PatchCommand::Code {
+                account_id,
+                wasm_file,
+            } => {
+                let wasm = std::fs::read(&wasm_file)
+                    .with_context(|| format!("reading wasm file {}", wasm_file.display()))?;
+                nearcore_ctx.patch_code(&account_id, &wasm).await?;
+            }
+            PatchCommand::Balance {
+                account_id,
+                near,
+                yoctonear,
+            } => {
+                let balance = match (near, yoctonear) {
+                    (Some(near), _) => near
+                        .checked_mul(NearToken::from_near(1).as_yoctonear())
+                        .map(NearToken::from_yoctonear)
+                        .with_context(|| format!("{} NEAR does not fit in a yoctoNEAR balance", near))?,
+                    (None, Some(yoctonear)) => NearToken::from_yoctonear(yoctonear),
+                    (None, None) => unreachable!("enforced by clap"),
+                };
+                nearcore_ctx.set_balance(&account_id, balance).await?;
+            }
+            PatchCommand::AccessKey {
+                account_id,
+                public_key,
+            } => {
+                let (public_key, secret_key) = match public_key {
+                    Some(public_key) => (public_key, None),
+                    None => {
+                        let secret_key = SecretKey::from_random(KeyType::ED25519);
+                        (secret_key.public_key(), Some(secret_key))
+                    }
+                };
+                nearcore_ctx
+                    .add_access_key(&account_id, public_key, AccessKey::full_access())
+                    .await?;
+                return Ok(secret_key.map(|sk| (account_id, sk)));
+            }
+        }
+
+        Ok(None)
+    }
+}
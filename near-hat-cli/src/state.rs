@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Subcommand;
+use near_hat::AccountDump;
+use near_primitives::types::AccountId;
+
+use crate::ConnectArgs;
+
+#[derive(Subcommand, Debug)]
+pub enum StateCommand {
+    /// Dump account balance, keys, code hash and storage to JSON.
+    Dump {
+        #[command(flatten)]
+        connect: ConnectArgs,
+        account_id: AccountId,
+        /// Output file, defaults to stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Show changed, added and removed keys between two dumps.
+    Diff {
+        before: PathBuf,
+        after: PathBuf,
+        /// Print the diff as JSON instead of a human readable list.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn read_dump(path: &PathBuf) -> anyhow::Result<AccountDump> {
+    let json_str = std::fs::read_to_string(path)
+        .with_context(|| format!("reading state dump {}", path.display()))?;
+    serde_json::from_str(&json_str).with_context(|| format!("parsing state dump {}", path.display()))
+}
+
+impl StateCommand {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            StateCommand::Dump {
+                connect,
+                account_id,
+                output,
+            } => {
+                let nearcore_ctx = crate::connect(&connect).await?;
+                let dump = nearcore_ctx.dump_account(&account_id).await?;
+                let json_str = serde_json::to_string_pretty(&dump)?;
+                match output {
+                    Some(path) => std::fs::write(&path, json_str)
+                        .with_context(|| format!("writing state dump {}", path.display()))?,
+                    None => println!("{json_str}"),
+                }
+            }
+            StateCommand::Diff {
+                before,
+                after,
+                json,
+            } => {
+                let diff = read_dump(&before)?.diff(&read_dump(&after)?);
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&diff)?);
+                } else {
+                    print!("{diff}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
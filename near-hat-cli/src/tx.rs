@@ -0,0 +1,49 @@
+use clap::Subcommand;
+use near_hat::{TxTracer, DEFAULT_SEARCH_DEPTH};
+
+use crate::lake::BucketArgs;
+
+#[derive(Subcommand, Debug)]
+pub enum TxCommand {
+    /// Print the receipt tree of a transaction: actions, gas burnt, logs, failures and refunds.
+    Trace {
+        #[command(flatten)]
+        bucket: BucketArgs,
+        hash: String,
+        /// Search for the transaction from this block upwards instead of from the latest block down.
+        #[arg(long)]
+        from_block: Option<u64>,
+        /// Number of blocks below the latest one to search.
+        #[arg(long, default_value_t = DEFAULT_SEARCH_DEPTH)]
+        search_depth: u64,
+        /// Print the trace as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+impl TxCommand {
+    pub async fn run(self) -> anyhow::Result<()> {
+        match self {
+            TxCommand::Trace {
+                bucket,
+                hash,
+                from_block,
+                search_depth,
+                json,
+            } => {
+                let trace = TxTracer::new(bucket.bucket())
+                    .search_depth(search_depth)
+                    .trace(&hash, from_block)
+                    .await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&trace)?);
+                } else {
+                    print!("{}", trace);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
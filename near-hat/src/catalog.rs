@@ -0,0 +1,121 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context};
+use serde_json::{json, Value};
+
+/// Commonly needed contracts that can be pre-deployed from vendored wasm in `wasm/`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StandardContract {
+    /// Top-level `near` linkdrop, required for creating `*.near` accounts.
+    Linkdrop,
+    /// `social.near` SocialDB used by BOS.
+    SocialDb,
+    /// Reference NEP-141 fungible token.
+    FungibleToken,
+    /// Reference NEP-171 non-fungible token.
+    NonFungibleToken,
+    /// Multisig wallet with the contract account as its only member.
+    Multisig,
+    /// `wrap.near` wrapped NEAR token.
+    WrapNear,
+}
+
+impl StandardContract {
+    pub const ALL: [StandardContract; 6] = [
+        StandardContract::Linkdrop,
+        StandardContract::SocialDb,
+        StandardContract::FungibleToken,
+        StandardContract::NonFungibleToken,
+        StandardContract::Multisig,
+        StandardContract::WrapNear,
+    ];
+
+    pub fn account_id(&self) -> &'static str {
+        match self {
+            StandardContract::Linkdrop => "near",
+            StandardContract::SocialDb => "social.near",
+            StandardContract::FungibleToken => "ft.near",
+            StandardContract::NonFungibleToken => "nft.near",
+            StandardContract::Multisig => "multisig.near",
+            StandardContract::WrapNear => "wrap.near",
+        }
+    }
+
+    pub fn wasm_path(&self) -> &'static str {
+        match self {
+            StandardContract::Linkdrop => "wasm/linkdrop.wasm",
+            StandardContract::SocialDb => "wasm/social_db.wasm",
+            StandardContract::FungibleToken => "wasm/fungible_token.wasm",
+            StandardContract::NonFungibleToken => "wasm/non_fungible_token.wasm",
+            StandardContract::Multisig => "wasm/multisig.wasm",
+            StandardContract::WrapNear => "wasm/w_near.wasm",
+        }
+    }
+
+    pub fn wasm(&self) -> anyhow::Result<Vec<u8>> {
+        std::fs::read(self.wasm_path())
+            .with_context(|| format!("reading vendored {} contract from {} (see wasm/README.md)", self, self.wasm_path()))
+    }
+
+    /// Calls (method, JSON args) needed to bring a freshly deployed contract into a usable state.
+    pub fn init_calls(&self) -> Vec<(&'static str, Value)> {
+        let account_id = self.account_id();
+        match self {
+            StandardContract::Linkdrop => vec![("new", json!({}))],
+            StandardContract::SocialDb => vec![
+                ("new", json!({})),
+                ("set_status", json!({ "status": "Live" })),
+            ],
+            StandardContract::FungibleToken => vec![(
+                "new_default_meta",
+                json!({
+                    "owner_id": account_id,
+                    "total_supply": "1000000000000000000000000000000000",
+                }),
+            )],
+            StandardContract::NonFungibleToken => {
+                vec![("new_default_meta", json!({ "owner_id": account_id }))]
+            }
+            StandardContract::Multisig => vec![(
+                "new",
+                json!({
+                    "members": [{ "account_id": account_id }],
+                    "num_confirmations": 1,
+                }),
+            )],
+            StandardContract::WrapNear => vec![("new", json!({}))],
+        }
+    }
+}
+
+impl fmt::Display for StandardContract {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            StandardContract::Linkdrop => "linkdrop",
+            StandardContract::SocialDb => "social-db",
+            StandardContract::FungibleToken => "ft",
+            StandardContract::NonFungibleToken => "nft",
+            StandardContract::Multisig => "multisig",
+            StandardContract::WrapNear => "wrap-near",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for StandardContract {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        StandardContract::ALL
+            .into_iter()
+            .find(|contract| contract.to_string() == s)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown standard contract '{}', expected one of: {}",
+                    s,
+                    StandardContract::ALL.map(|contract| contract.to_string()).join(", ")
+                )
+            })
+    }
+}
@@ -1,10 +1,14 @@
 use anyhow::anyhow;
+use bollard::container::LogOutput;
+use bollard::exec::{CreateExecOptions, StartExecResults};
 use bollard::network::CreateNetworkOptions;
 use bollard::service::Ipam;
 use bollard::Docker;
 use futures::lock::Mutex;
+use futures::StreamExt;
 use once_cell::sync::Lazy;
 use std::path::Path;
+use std::time::Duration;
 use testcontainers::clients::Cli;
 use testcontainers::{Container, Image};
 
@@ -56,6 +60,57 @@ impl DockerClient {
         Ok(ip_address)
     }
 
+    /// Run a command inside a running container and return its stdout. Fails if the command
+    /// exits with a non-zero status.
+    pub async fn exec_output(&self, container_id: &str, cmd: Vec<&str>) -> anyhow::Result<String> {
+        let command = cmd.join(" ");
+        let create_result = self
+            .docker
+            .create_exec(
+                container_id,
+                CreateExecOptions {
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    cmd: Some(cmd),
+                    ..Default::default()
+                },
+            )
+            .await?;
+
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        if let StartExecResults::Attached { mut output, .. } =
+            self.docker.start_exec(&create_result.id, None).await?
+        {
+            while let Some(chunk) = output.next().await {
+                match chunk? {
+                    LogOutput::StdOut { message } => stdout.push_str(&String::from_utf8_lossy(&message)),
+                    LogOutput::StdErr { message } => stderr.push_str(&String::from_utf8_lossy(&message)),
+                    _ => {}
+                }
+            }
+        }
+
+        // The output stream can end before Docker has recorded the exit code.
+        let exit_code = loop {
+            let exec = self.docker.inspect_exec(&create_result.id).await?;
+            if exec.running != Some(true) {
+                break exec.exit_code;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        };
+        if exit_code != Some(0) {
+            return Err(anyhow!(
+                "`{}` in container '{}' exited with {:?}: {}",
+                command,
+                container_id,
+                exit_code,
+                stderr.trim()
+            ));
+        }
+        Ok(stdout)
+    }
+
     pub async fn create_network(&self, network: &str) -> anyhow::Result<()> {
         let _lock = &NETWORK_MUTEX.lock().await;
         let list = self.docker.list_networks::<&str>(None).await?;
@@ -0,0 +1,25 @@
+use crate::catalog::StandardContract;
+use crate::containers::lake_indexer::LakeIndexerOptions;
+use crate::containers::lake_storage::LakeStorageKind;
+use crate::containers::relayer::RelayerPolicy;
+use crate::ctx::explorer::ExplorerConfig;
+use crate::ctx::relayer::RelayerInstanceConfig;
+
+/// Options selecting what a NEARHat environment provisions on startup.
+#[derive(Clone, Debug, Default)]
+pub struct NearHatConfig {
+    /// Standard contracts to pre-deploy from vendored wasm in `wasm/`.
+    pub standard_contracts: Vec<StandardContract>,
+    /// Relayer whitelists, feature flags and allowances.
+    pub relayer: RelayerPolicy,
+    /// Deploy a local social DB (from `wasm/social_db.wasm`) and use it for relayer shared storage.
+    pub relayer_social_db: bool,
+    /// Additional named relayers, each served at `relayer-<name>.nearhat`.
+    pub relayer_instances: Vec<RelayerInstanceConfig>,
+    /// S3 server backing the Lake bucket.
+    pub lake_storage: LakeStorageKind,
+    /// Sync mode, concurrency and finality of the Lake indexer.
+    pub lake_indexer: LakeIndexerOptions,
+    /// Which explorer components to start and the external services they use.
+    pub explorer: ExplorerConfig,
+}
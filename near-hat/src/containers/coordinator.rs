@@ -1,3 +1,4 @@
+use crate::lake::{FAKE_ACCESS_KEY_ID, FAKE_SECRET_ACCESS_KEY};
 use crate::DockerClient;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
@@ -22,8 +23,8 @@ impl<'a> Coordinator<'a> {
         tracing::info!(network, "starting Coordinator container");
 
         let image = GenericImage::new("darunrs/queryapi", "coordinator")
-            .with_env_var("AWS_ACCESS_KEY_ID", "FAKE_LOCALSTACK_KEY_ID")
-            .with_env_var("AWS_SECRET_ACCESS_KEY", "FAKE_LOCALSTACK_ACCESS_KEY")
+            .with_env_var("AWS_ACCESS_KEY_ID", FAKE_ACCESS_KEY_ID)
+            .with_env_var("AWS_SECRET_ACCESS_KEY", FAKE_SECRET_ACCESS_KEY)
             .with_env_var("AWS_REGION", s3_region)
             .with_env_var("S3_URL", s3_address)
             .with_env_var("S3_BUCKET_NAME", s3_bucket_name)
@@ -2,6 +2,32 @@ use crate::DockerClient;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
+/// Connection to one of the Postgres databases the explorer backend reads from.
+#[derive(Clone, Debug)]
+pub struct ExplorerDbConfig {
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+    pub user: String,
+    pub password: String,
+}
+
+impl ExplorerDbConfig {
+    /// Backend environment variables for the `NEAR_EXPLORER_CONFIG__DB__<name>` database.
+    fn env_vars(&self, name: &str) -> Vec<(String, String)> {
+        [
+            ("HOST", self.host.clone()),
+            ("PORT", self.port.to_string()),
+            ("DATABASE", self.database.clone()),
+            ("USER", self.user.clone()),
+            ("PASSWORD", self.password.clone()),
+        ]
+        .into_iter()
+        .map(|(field, value)| (format!("NEAR_EXPLORER_CONFIG__DB__{name}__{field}"), value))
+        .collect()
+    }
+}
+
 pub struct ExplorerBackend<'a> {
     pub container: Container<'a, GenericImage>,
     pub ip_address: String,
@@ -14,67 +40,32 @@ impl<'a> ExplorerBackend<'a> {
     pub async fn run(
         docker_client: &'a DockerClient,
         network: &str,
-        database_host: &str,
-        database_port: u16,
+        indexer_db: &ExplorerDbConfig,
+        analytics_db: &ExplorerDbConfig,
+        telemetry_db: &ExplorerDbConfig,
         rpc_url: &str,
     ) -> anyhow::Result<ExplorerBackend<'a>> {
-        tracing::info!(network, "starting NEAR Explorer Backend container");
+        tracing::info!(
+            network,
+            analytics_db = %analytics_db.host,
+            telemetry_db = %telemetry_db.host,
+            "starting NEAR Explorer Backend container"
+        );
 
-        let image = GenericImage::new("morgsmccauley/explorer-backend", "latest")
+        let mut image = GenericImage::new("morgsmccauley/explorer-backend", "latest")
             .with_env_var("NEAR_EXPLORER_CONFIG__ARCHIVAL_RPC_URL", rpc_url)
-            .with_env_var("NEAR_EXPLORER_CONFIG__NETWORK_NAME", "localnet")
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_INDEXER__HOST",
-                database_host,
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_INDEXER__DATABASE",
-                "postgres",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_INDEXER__USER",
-                "postgres",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_INDEXER__PASSWORD",
-                "postgres",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_INDEXER__PORT",
-                database_port.to_string(),
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_ANALYTICS__HOST",
-                "34.78.19.198",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_ANALYTICS__DATABASE",
-                "indexer_analytics_mainnet",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_ANALYTICS__USER",
-                "public_readonly",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_ANALYTICS__PASSWORD",
-                "nearprotocol",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_TELEMETRY__HOST",
-                "34.78.19.198",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_TELEMETRY__DATABASE",
-                "telemetry_mainnet",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_TELEMETRY__USER",
-                "public_readonly",
-            )
-            .with_env_var(
-                "NEAR_EXPLORER_CONFIG__DB__READ_ONLY_TELEMETRY__PASSWORD",
-                "nearprotocol",
-            )
+            .with_env_var("NEAR_EXPLORER_CONFIG__NETWORK_NAME", "localnet");
+        for (name, db) in [
+            ("READ_ONLY_INDEXER", indexer_db),
+            ("READ_ONLY_ANALYTICS", analytics_db),
+            ("READ_ONLY_TELEMETRY", telemetry_db),
+        ] {
+            for (key, value) in db.env_vars(name) {
+                image = image.with_env_var(key, value);
+            }
+        }
+        // An empty host disables writing telemetry.
+        let image = image
             .with_env_var("NEAR_EXPLORER_CONFIG__DB__WRITE_ONLY_TELEMETRY__HOST", "")
             .with_env_var(
                 "NEAR_EXPLORER_CONFIG__DB__WRITE_ONLY_TELEMETRY__DATABASE",
@@ -1,4 +1,5 @@
 use crate::DockerClient;
+use serde_json::json;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
@@ -16,14 +17,23 @@ impl<'a> ExplorerFrontend<'a> {
         backend_host_port: u16,
         backend_internal_ip: &str,
         backend_internal_port: u16,
+        segment_write_key: &str,
+        explorer_link: &str,
+        wallet_profile_prefix: &str,
     ) -> anyhow::Result<ExplorerFrontend<'a>> {
         tracing::info!(network, "starting NEAR Explorer Frontend container");
 
         let image = GenericImage::new("morgsmccauley/explorer-frontend", "latest")
-            .with_env_var("NEAR_EXPLORER_CONFIG__SEGMENT_WRITE_KEY", "7s4Na9mAfC7092R6pxrwpfBIAEek9Dne")
+            .with_env_var("NEAR_EXPLORER_CONFIG__SEGMENT_WRITE_KEY", segment_write_key)
             .with_env_var(
                 "NEAR_EXPLORER_CONFIG__NETWORKS",
-                "{ \"localnet\": { \"explorerLink\": \"http://localhost:3000\", \"nearWalletProfilePrefix\": \"https://wallet.near.org/profile\" } }",
+                json!({
+                    "localnet": {
+                        "explorerLink": explorer_link,
+                        "nearWalletProfilePrefix": wallet_profile_prefix,
+                    }
+                })
+                .to_string(),
             )
             .with_env_var("NEAR_EXPLORER_CONFIG__BACKEND__HOSTS__MAINNET", "")
             .with_env_var(
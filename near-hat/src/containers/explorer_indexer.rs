@@ -1,3 +1,4 @@
+use crate::lake::{FAKE_ACCESS_KEY_ID, FAKE_SECRET_ACCESS_KEY};
 use crate::DockerClient;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
@@ -25,8 +26,8 @@ impl<'a> ExplorerIndexer<'a> {
         );
 
         let image = GenericImage::new("morgsmccauley/explorer-indexer", "latest")
-            .with_env_var("AWS_ACCESS_KEY_ID", "FAKE_LOCALSTACK_KEY_ID")
-            .with_env_var("AWS_SECRET_ACCESS_KEY", "FAKE_LOCALSTACK_ACCESS_KEY")
+            .with_env_var("AWS_ACCESS_KEY_ID", FAKE_ACCESS_KEY_ID)
+            .with_env_var("AWS_SECRET_ACCESS_KEY", FAKE_SECRET_ACCESS_KEY)
             .with_env_var("DATABASE_URL", database_url)
             .with_env_var("S3_REGION", s3_region)
             .with_env_var("AWS_REGION", s3_region)
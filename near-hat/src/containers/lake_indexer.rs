@@ -1,13 +1,147 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::lake::{FAKE_ACCESS_KEY_ID, FAKE_SECRET_ACCESS_KEY};
 use crate::validator::ValidatorContainer;
 use crate::DockerClient;
+use anyhow::{anyhow, Context};
+use bollard::container::{Config, RenameContainerOptions};
+use bollard::image::CommitContainerOptions;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
+/// Where the Lake indexer starts streaming blocks from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    #[default]
+    FromLatest,
+    /// Continue from the last streamed block, useful after a restart.
+    FromInterruption,
+    FromBlock(u64),
+}
+
+impl fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncMode::FromLatest => f.write_str("latest"),
+            SyncMode::FromInterruption => f.write_str("interruption"),
+            SyncMode::FromBlock(height) => write!(f, "block:{height}"),
+        }
+    }
+}
+
+impl FromStr for SyncMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "latest" => Ok(SyncMode::FromLatest),
+            "interruption" => Ok(SyncMode::FromInterruption),
+            _ => s
+                .strip_prefix("block:")
+                .and_then(|height| height.parse().ok())
+                .map(SyncMode::FromBlock)
+                .ok_or_else(|| {
+                    anyhow!("unknown sync mode '{}', expected latest, interruption or block:<height>", s)
+                }),
+        }
+    }
+}
+
+/// Finality of the blocks the Lake indexer streams.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LakeFinality {
+    Optimistic,
+    NearFinal,
+    Final,
+}
+
+impl fmt::Display for LakeFinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LakeFinality::Optimistic => f.write_str("optimistic"),
+            LakeFinality::NearFinal => f.write_str("near-final"),
+            LakeFinality::Final => f.write_str("final"),
+        }
+    }
+}
+
+impl FromStr for LakeFinality {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "optimistic" => Ok(LakeFinality::Optimistic),
+            "near-final" => Ok(LakeFinality::NearFinal),
+            "final" => Ok(LakeFinality::Final),
+            _ => Err(anyhow!(
+                "unknown finality '{}', expected optimistic, near-final or final",
+                s
+            )),
+        }
+    }
+}
+
+/// Command line options of the Lake indexer. `None` keeps the indexer's own default.
+#[derive(Clone, Debug)]
+pub struct LakeIndexerOptions {
+    pub sync_mode: SyncMode,
+    pub stream_while_syncing: bool,
+    pub concurrency: Option<u16>,
+    pub finality: Option<LakeFinality>,
+}
+
+impl Default for LakeIndexerOptions {
+    fn default() -> Self {
+        LakeIndexerOptions {
+            sync_mode: SyncMode::FromLatest,
+            stream_while_syncing: true,
+            concurrency: None,
+            finality: None,
+        }
+    }
+}
+
+impl LakeIndexerOptions {
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if self.stream_while_syncing {
+            args.push("--stream-while-syncing".to_string());
+        }
+        if let Some(concurrency) = self.concurrency {
+            args.extend(["--concurrency".to_string(), concurrency.to_string()]);
+        }
+        if let Some(finality) = self.finality {
+            args.extend(["--finality".to_string(), finality.to_string()]);
+        }
+        match self.sync_mode {
+            SyncMode::FromLatest => args.push("sync-from-latest".to_string()),
+            SyncMode::FromInterruption => args.push("sync-from-interruption".to_string()),
+            SyncMode::FromBlock(height) => args.extend([
+                "sync-from-block".to_string(),
+                "--height".to_string(),
+                height.to_string(),
+            ]),
+        }
+        args
+    }
+}
+
+/// Image repository of the snapshots [`LakeIndexer::restart`] replaces the container with.
+const SNAPSHOT_REPOSITORY: &str = "nearhat-lake-indexer-snapshot";
+
 pub struct LakeIndexer<'a> {
     pub container: Container<'a, GenericImage>,
     pub bucket_name: String,
     pub region: String,
+    /// RPC address inside the Docker network. It uses the container name rather than its IP,
+    /// so it stays valid across [`LakeIndexer::restart`].
     pub rpc_address: String,
+    pub options: LakeIndexerOptions,
+    network: String,
+    container_name: String,
+    args: Vec<String>,
+    snapshot_tag: Option<String>,
 }
 
 impl<'a> LakeIndexer<'a> {
@@ -19,40 +153,39 @@ impl<'a> LakeIndexer<'a> {
         s3_address: &str,
         bucket_name: String,
         region: String,
+        options: &LakeIndexerOptions,
     ) -> anyhow::Result<LakeIndexer<'a>> {
         tracing::info!(
             network,
             s3_address,
             bucket_name,
             region,
+            ?options,
             "Starting NEAR RPC with Lake Indexer container"
         );
 
-        let image = GenericImage::new("ghcr.io/near/near-lake-indexer", "latest-sandbox")
-            .with_env_var("AWS_ACCESS_KEY_ID", "FAKE_LOCALSTACK_KEY_ID")
-            .with_env_var("AWS_SECRET_ACCESS_KEY", "FAKE_LOCALSTACK_ACCESS_KEY")
-            .with_wait_for(WaitFor::message_on_stderr("Starting Streamer"))
-            .with_exposed_port(Self::CONTAINER_RPC_PORT);
-        let image: RunnableImage<GenericImage> = (
-            image,
-            vec![
-                "--endpoint".to_string(),
-                s3_address.to_string(),
-                "--bucket".to_string(),
-                bucket_name.clone(),
-                "--region".to_string(),
-                region.clone(),
-                "--stream-while-syncing".to_string(),
-                "sync-from-latest".to_string(),
-            ],
-        )
-            .into();
-        let image = image.with_network(network);
+        let mut args = vec![
+            "--endpoint".to_string(),
+            s3_address.to_string(),
+            "--bucket".to_string(),
+            bucket_name.clone(),
+            "--region".to_string(),
+            region.clone(),
+        ];
+        args.extend(options.args());
+        // Container names resolve inside the Docker network, the random suffix keeps several
+        // environments on one Docker host apart.
+        let container_name = format!(
+            "lake-indexer-{}",
+            rand::Rng::sample_iter(rand::thread_rng(), &rand::distributions::Alphanumeric)
+                .take(8)
+                .map(|c| char::from(c).to_ascii_lowercase())
+                .collect::<String>()
+        );
+        let image = Self::image("ghcr.io/near/near-lake-indexer", "latest-sandbox", &args, network)
+            .with_container_name(&container_name);
         let container = docker_client.cli.run(image);
-        let ip_address = docker_client
-            .get_network_ip_address(&container, network)
-            .await?;
-        let rpc_address = format!("http://{}:{}", ip_address, Self::CONTAINER_RPC_PORT);
+        let rpc_address = format!("http://{}:{}", container_name, Self::CONTAINER_RPC_PORT);
 
         tracing::info!(
             bucket_name,
@@ -66,9 +199,95 @@ impl<'a> LakeIndexer<'a> {
             bucket_name,
             region,
             rpc_address,
+            options: options.clone(),
+            network: network.to_string(),
+            container_name,
+            args,
+            snapshot_tag: None,
         })
     }
 
+    fn image(name: &str, tag: &str, args: &[String], network: &str) -> RunnableImage<GenericImage> {
+        let image = GenericImage::new(name, tag)
+            .with_env_var("AWS_ACCESS_KEY_ID", FAKE_ACCESS_KEY_ID)
+            .with_env_var("AWS_SECRET_ACCESS_KEY", FAKE_SECRET_ACCESS_KEY)
+            .with_wait_for(WaitFor::message_on_stderr("Starting Streamer"))
+            .with_exposed_port(Self::CONTAINER_RPC_PORT);
+        let image: RunnableImage<GenericImage> = (image, args.to_vec()).into();
+        image.with_network(network)
+    }
+
+    /// Restart the node and Lake indexer process. Chain state survives the restart, and the
+    /// indexer resumes according to its [`SyncMode`], so use [`SyncMode::FromInterruption`] to
+    /// test catching up.
+    ///
+    /// Docker assigns unpinned ports anew on every start, so rather than starting the stopped
+    /// container again, it is replaced by a snapshot of itself bound to the same host RPC port
+    /// and container name, so both the host address and [`LakeIndexer::rpc_address`] used by
+    /// the other containers keep working. Volumes are not part of the snapshot and are mounted
+    /// into the replacement instead. The snapshot image of the last restart is left behind as
+    /// `nearhat-lake-indexer-snapshot`.
+    pub async fn restart(&mut self, docker_client: &'a DockerClient) -> anyhow::Result<()> {
+        tracing::info!(rpc_address = self.rpc_address, "restarting NEAR RPC with Lake Indexer");
+        let host_rpc_port = self.host_rpc_port_ipv4();
+        self.container.stop();
+        let volumes: Vec<(String, String)> = docker_client
+            .docker
+            .inspect_container(self.container.id(), None)
+            .await?
+            .mounts
+            .into_iter()
+            .flatten()
+            // Only volumes are named, bind mounts are not.
+            .filter_map(|mount| Some((mount.name?, mount.destination?)))
+            .collect();
+
+        let snapshot_tag = self.container.id()[..12].to_string();
+        docker_client
+            .docker
+            .commit_container(
+                CommitContainerOptions {
+                    container: self.container.id(),
+                    repo: SNAPSHOT_REPOSITORY,
+                    tag: snapshot_tag.as_str(),
+                    ..Default::default()
+                },
+                Config::<String>::default(),
+            )
+            .await
+            .context("snapshotting the Lake indexer container")?;
+        // Free the name for the replacement, the stopped container is removed once replaced.
+        docker_client
+            .docker
+            .rename_container(
+                self.container.id(),
+                RenameContainerOptions {
+                    name: format!("{}-{}", self.container_name, snapshot_tag),
+                },
+            )
+            .await
+            .context("renaming the stopped Lake indexer container")?;
+        let mut image = Self::image(SNAPSHOT_REPOSITORY, &snapshot_tag, &self.args, &self.network)
+            .with_mapped_port((host_rpc_port, Self::CONTAINER_RPC_PORT))
+            .with_container_name(&self.container_name);
+        for volume in volumes {
+            image = image.with_volume(volume);
+        }
+        self.container = docker_client.cli.run(image);
+
+        // The previous snapshot was only used by the container that was just removed.
+        if let Some(previous_tag) = self.snapshot_tag.replace(snapshot_tag) {
+            docker_client
+                .docker
+                .remove_image(&format!("{SNAPSHOT_REPOSITORY}:{previous_tag}"), None, None)
+                .await
+                .context("removing the previous Lake indexer snapshot")?;
+        }
+
+        tracing::info!(rpc_address = self.rpc_address, "NEAR RPC with Lake Indexer restarted");
+        Ok(())
+    }
+
     pub fn host_rpc_port_ipv4(&self) -> u16 {
         return self.container.get_host_port_ipv4(Self::CONTAINER_RPC_PORT);
     }
@@ -89,3 +308,39 @@ impl<'a> ValidatorContainer<'a> for LakeIndexer<'a> {
         &self.container
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_mode_round_trips() {
+        for sync_mode in [
+            SyncMode::FromLatest,
+            SyncMode::FromInterruption,
+            SyncMode::FromBlock(0),
+            SyncMode::FromBlock(123_456),
+        ] {
+            assert_eq!(sync_mode.to_string().parse::<SyncMode>().unwrap(), sync_mode);
+        }
+    }
+
+    #[test]
+    fn sync_mode_rejects_unknown_values() {
+        for value in ["", "block:", "block:abc", "block:-1", "from-latest"] {
+            assert!(value.parse::<SyncMode>().is_err(), "{value}");
+        }
+    }
+
+    #[test]
+    fn lake_finality_round_trips() {
+        for finality in [
+            LakeFinality::Optimistic,
+            LakeFinality::NearFinal,
+            LakeFinality::Final,
+        ] {
+            assert_eq!(finality.to_string().parse::<LakeFinality>().unwrap(), finality);
+        }
+        assert!("near_final".parse::<LakeFinality>().is_err());
+    }
+}
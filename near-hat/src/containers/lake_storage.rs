@@ -0,0 +1,127 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::containers::localstack::LocalStack;
+use crate::containers::minio::Minio;
+use crate::lake::LakeS3Config;
+use crate::lake_bucket::LakeBucket;
+use crate::DockerClient;
+
+/// S3-compatible server backing the Lake bucket.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LakeStorageKind {
+    #[default]
+    LocalStack,
+    /// Starts faster and uses much less memory than LocalStack.
+    Minio,
+}
+
+impl fmt::Display for LakeStorageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LakeStorageKind::LocalStack => f.write_str("localstack"),
+            LakeStorageKind::Minio => f.write_str("minio"),
+        }
+    }
+}
+
+impl FromStr for LakeStorageKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "localstack" => Ok(LakeStorageKind::LocalStack),
+            "minio" => Ok(LakeStorageKind::Minio),
+            _ => Err(anyhow!(
+                "unknown lake storage '{}', expected one of: localstack, minio",
+                s
+            )),
+        }
+    }
+}
+
+/// Running Lake bucket, whichever server it is backed by.
+pub enum LakeStorage<'a> {
+    LocalStack(LocalStack<'a>),
+    Minio(Minio<'a>),
+}
+
+impl<'a> LakeStorage<'a> {
+    /// Start the storage server, create the bucket and check that it is reachable.
+    pub async fn run(
+        docker_client: &'a DockerClient,
+        network: &str,
+        kind: LakeStorageKind,
+        s3_bucket: String,
+        s3_region: String,
+    ) -> anyhow::Result<LakeStorage<'a>> {
+        let storage = match kind {
+            LakeStorageKind::LocalStack => LakeStorage::LocalStack(
+                LocalStack::run(docker_client, network, s3_bucket, s3_region).await?,
+            ),
+            LakeStorageKind::Minio => {
+                LakeStorage::Minio(Minio::run(docker_client, network, s3_bucket, s3_region).await?)
+            }
+        };
+        storage.bucket().check_accessible().await?;
+        Ok(storage)
+    }
+
+    pub fn kind(&self) -> LakeStorageKind {
+        match self {
+            LakeStorage::LocalStack(_) => LakeStorageKind::LocalStack,
+            LakeStorage::Minio(_) => LakeStorageKind::Minio,
+        }
+    }
+
+    /// S3 address reachable from the docker network.
+    pub fn s3_address(&self) -> &str {
+        match self {
+            LakeStorage::LocalStack(localstack) => &localstack.s3_address,
+            LakeStorage::Minio(minio) => &minio.s3_address,
+        }
+    }
+
+    pub fn s3_bucket(&self) -> &str {
+        match self {
+            LakeStorage::LocalStack(localstack) => &localstack.s3_bucket,
+            LakeStorage::Minio(minio) => &minio.s3_bucket,
+        }
+    }
+
+    pub fn s3_region(&self) -> &str {
+        match self {
+            LakeStorage::LocalStack(localstack) => &localstack.s3_region,
+            LakeStorage::Minio(minio) => &minio.s3_region,
+        }
+    }
+
+    pub fn host_port_ipv4(&self) -> u16 {
+        match self {
+            LakeStorage::LocalStack(localstack) => localstack.host_port_ipv4(),
+            LakeStorage::Minio(minio) => minio.host_port_ipv4(),
+        }
+    }
+
+    pub fn host_s3_address_ipv4(&self) -> String {
+        match self {
+            LakeStorage::LocalStack(localstack) => localstack.host_s3_address_ipv4(),
+            LakeStorage::Minio(minio) => minio.host_s3_address_ipv4(),
+        }
+    }
+
+    /// Lake bucket location, reachable from the host.
+    pub fn lake_s3_config(&self) -> LakeS3Config {
+        LakeS3Config {
+            endpoint: self.host_s3_address_ipv4(),
+            bucket: self.s3_bucket().to_string(),
+            region: self.s3_region().to_string(),
+        }
+    }
+
+    pub fn bucket(&self) -> LakeBucket {
+        LakeBucket::from_config(&self.lake_s3_config())
+    }
+}
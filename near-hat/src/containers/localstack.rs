@@ -1,5 +1,5 @@
 use crate::DockerClient;
-use bollard::exec::CreateExecOptions;
+use anyhow::Context;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
@@ -32,30 +32,21 @@ impl<'a> LocalStack<'a> {
         let container = docker_client.cli.run(image);
 
         // Create the bucket
-        let create_result = docker_client
-            .docker
-            .create_exec(
+        docker_client
+            .exec_output(
                 container.id(),
-                CreateExecOptions::<&str> {
-                    attach_stdout: Some(true),
-                    attach_stderr: Some(true),
-                    cmd: Some(vec![
-                        "awslocal",
-                        "s3api",
-                        "create-bucket",
-                        "--bucket",
-                        &s3_bucket,
-                        "--region",
-                        &s3_region,
-                    ]),
-                    ..Default::default()
-                },
+                vec![
+                    "awslocal",
+                    "s3api",
+                    "create-bucket",
+                    "--bucket",
+                    &s3_bucket,
+                    "--region",
+                    &s3_region,
+                ],
             )
-            .await?;
-        docker_client
-            .docker
-            .start_exec(&create_result.id, None)
-            .await?;
+            .await
+            .context("creating LocalStack bucket")?;
 
         let ip_address = docker_client
             .get_network_ip_address(&container, network)
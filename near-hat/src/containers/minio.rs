@@ -0,0 +1,77 @@
+use crate::lake::{FAKE_ACCESS_KEY_ID, FAKE_SECRET_ACCESS_KEY};
+use crate::DockerClient;
+use anyhow::Context;
+use testcontainers::core::WaitFor;
+use testcontainers::{Container, GenericImage, RunnableImage};
+
+/// MinIO S3 server, a much smaller alternative to LocalStack for the Lake bucket.
+pub struct Minio<'a> {
+    pub container: Container<'a, GenericImage>,
+    pub s3_address: String,
+    pub s3_bucket: String,
+    pub s3_region: String,
+}
+
+impl<'a> Minio<'a> {
+    const S3_CONTAINER_PORT: u16 = 9000;
+
+    pub async fn run(
+        docker_client: &'a DockerClient,
+        network: &str,
+        s3_bucket: String,
+        s3_region: String,
+    ) -> anyhow::Result<Minio<'a>> {
+        tracing::info!(network, s3_bucket, s3_region, "Starting MinIO container");
+        // Root credentials match the fake Lake credentials used by every Lake client.
+        let image = GenericImage::new("minio/minio", "latest")
+            .with_env_var("MINIO_ROOT_USER", FAKE_ACCESS_KEY_ID)
+            .with_env_var("MINIO_ROOT_PASSWORD", FAKE_SECRET_ACCESS_KEY)
+            .with_env_var("MINIO_REGION", &s3_region)
+            .with_wait_for(WaitFor::message_on_stdout("API:"))
+            .with_exposed_port(Self::S3_CONTAINER_PORT);
+        let image: RunnableImage<GenericImage> = (
+            image,
+            vec!["server".to_string(), "/data".to_string()],
+        )
+            .into();
+        let image = image.with_network(network);
+        let container = docker_client.cli.run(image);
+
+        // Create the bucket and allow anonymous access, like LocalStack does
+        let setup = format!(
+            "mc alias set local http://127.0.0.1:{port} {user} {password} \
+             && mc mb --ignore-existing local/{bucket} \
+             && mc anonymous set public local/{bucket}",
+            port = Self::S3_CONTAINER_PORT,
+            user = FAKE_ACCESS_KEY_ID,
+            password = FAKE_SECRET_ACCESS_KEY,
+            bucket = s3_bucket,
+        );
+        docker_client
+            .exec_output(container.id(), vec!["sh", "-c", &setup])
+            .await
+            .context("creating MinIO bucket")?;
+
+        let ip_address = docker_client
+            .get_network_ip_address(&container, network)
+            .await?;
+        let s3_address = format!("http://{}:{}", ip_address, Self::S3_CONTAINER_PORT);
+        tracing::info!(s3_address, "MinIO container is running");
+
+        Ok(Minio {
+            container,
+            s3_address,
+            s3_bucket,
+            s3_region,
+        })
+    }
+
+    pub fn host_port_ipv4(&self) -> u16 {
+        self.container.get_host_port_ipv4(Self::S3_CONTAINER_PORT)
+    }
+
+    pub fn host_s3_address_ipv4(&self) -> String {
+        let host_port = self.container.get_host_port_ipv4(Self::S3_CONTAINER_PORT);
+        format!("http://127.0.0.1:{host_port}")
+    }
+}
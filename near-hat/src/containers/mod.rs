@@ -3,7 +3,9 @@ pub mod explorer_database;
 pub mod explorer_frontend;
 pub mod explorer_indexer;
 pub mod lake_indexer;
+pub mod lake_storage;
 pub mod localstack;
+pub mod minio;
 pub mod redis;
 pub mod relayer;
 pub mod sandbox;
@@ -1,4 +1,7 @@
 use crate::DockerClient;
+use anyhow::anyhow;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
@@ -31,6 +34,39 @@ impl<'a> Redis<'a> {
         })
     }
 
+    /// Address of a numbered Redis database, reachable from the docker network.
+    pub fn redis_db_address(&self, db: u32) -> String {
+        format!("{}/{}", self.redis_address, db)
+    }
+
+    /// Set a key in the given database through `redis-cli` inside the container.
+    pub async fn set(
+        &self,
+        docker_client: &DockerClient,
+        db: u32,
+        key: &str,
+        value: &str,
+    ) -> anyhow::Result<()> {
+        let db = db.to_string();
+        let reply = docker_client
+            .exec_output(
+                self.container.id(),
+                vec!["redis-cli", "-n", &db, "SET", key, value],
+            )
+            .await?;
+        // redis-cli exits with 0 on error replies, so check the reply as well.
+        if reply.trim() != "OK" {
+            return Err(anyhow!(
+                "setting Redis key '{}' in database {} failed: {}",
+                key,
+                db,
+                reply.trim()
+            ));
+        }
+        tracing::info!(db, key, value, "Redis key set");
+        Ok(())
+    }
+
     pub fn host_redis_address_ipv4(&self) -> String {
         let host_port = self
             .container
@@ -59,3 +95,41 @@ impl<'a> Redis<'a> {
         format!("http://[::1]:{host_port}")
     }
 }
+
+/// Members of a Redis set, queried over a plain TCP connection to `address`
+/// (`host:port`, optionally prefixed with `redis://` or `http://`).
+pub async fn set_members(address: &str, key: &str) -> anyhow::Result<Vec<String>> {
+    let address = address
+        .trim_start_matches("redis://")
+        .trim_start_matches("http://")
+        .trim_end_matches('/');
+    let mut stream = BufReader::new(TcpStream::connect(address).await?);
+    let command = format!("*2\r\n$8\r\nSMEMBERS\r\n${}\r\n{}\r\n", key.len(), key);
+    stream.get_mut().write_all(command.as_bytes()).await?;
+
+    let header = read_resp_line(&mut stream).await?;
+    let count: usize = header
+        .strip_prefix('*')
+        .ok_or_else(|| anyhow!("unexpected Redis reply to SMEMBERS: {header}"))?
+        .parse()?;
+    let mut members = Vec::with_capacity(count);
+    for _ in 0..count {
+        let header = read_resp_line(&mut stream).await?;
+        let len: usize = header
+            .strip_prefix('$')
+            .ok_or_else(|| anyhow!("unexpected Redis set member: {header}"))?
+            .parse()?;
+        // Bulk strings are terminated by CRLF, which is not part of the length.
+        let mut member = vec![0; len + 2];
+        stream.read_exact(&mut member).await?;
+        member.truncate(len);
+        members.push(String::from_utf8(member)?);
+    }
+    Ok(members)
+}
+
+async fn read_resp_line(stream: &mut BufReader<TcpStream>) -> anyhow::Result<String> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await?;
+    Ok(line.trim_end().to_string())
+}
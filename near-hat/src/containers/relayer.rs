@@ -2,37 +2,164 @@ use crate::DockerClient;
 use anyhow::Context;
 use near_primitives::types::AccountId;
 use near_workspaces::types::SecretKey;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
-use tempfile::{NamedTempFile, TempDir};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
 use testcontainers::core::WaitFor;
 use testcontainers::{Container, GenericImage, RunnableImage};
 use toml::Value;
 
-struct KeyFile {
-    file: NamedTempFile,
+/// Directory (relative to the relayer's working directory) holding the account key files.
+const KEYS_DIR: &str = "account_keys";
+
+/// Problems with a generated relayer config, detected before the container starts.
+#[derive(Debug)]
+pub enum RelayerConfigError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    NoRelayerKeys,
+    MissingKeyFile {
+        path: PathBuf,
+    },
+    InvalidKeyFile {
+        path: PathBuf,
+        reason: String,
+    },
+    KeyFileAccountMismatch {
+        path: PathBuf,
+        expected: AccountId,
+        found: String,
+    },
 }
 
-impl KeyFile {
-    pub fn temp_in_dir(
-        account_id: &AccountId,
-        account_sk: &SecretKey,
-        dir: &TempDir,
-    ) -> anyhow::Result<Self> {
-        // Field names are following the relayer's configuration format. Do not change.
-        let key_file_json = serde_json::json!({
-            "account_id": account_id,
-            "public_key": account_sk.public_key(),
-            "private_key": account_sk,
+impl fmt::Display for RelayerConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelayerConfigError::Io { path, source } => {
+                write!(f, "relayer config i/o error at {}: {}", path.display(), source)
+            }
+            RelayerConfigError::NoRelayerKeys => write!(f, "relayer config has no relayer keys"),
+            RelayerConfigError::MissingKeyFile { path } => {
+                write!(f, "relayer key file {} does not exist", path.display())
+            }
+            RelayerConfigError::InvalidKeyFile { path, reason } => {
+                write!(f, "relayer key file {} is invalid: {}", path.display(), reason)
+            }
+            RelayerConfigError::KeyFileAccountMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "relayer key file {} belongs to '{}', expected '{}'",
+                path.display(),
+                found,
+                expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RelayerConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RelayerConfigError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Write a key file in the relayer's format and return its path relative to the relayer's
+/// working directory.
+fn write_key_file(
+    app_dir: &Path,
+    filename: &str,
+    account_id: &AccountId,
+    account_sk: &SecretKey,
+) -> Result<PathBuf, RelayerConfigError> {
+    // Field names are following the relayer's configuration format. Do not change.
+    let key_file_json = serde_json::json!({
+        "account_id": account_id,
+        "public_key": account_sk.public_key(),
+        "private_key": account_sk,
+    });
+    let relative_path = Path::new(".").join(KEYS_DIR).join(filename);
+    let path = app_dir.join(&relative_path);
+    std::fs::write(&path, key_file_json.to_string())
+        .map_err(|source| RelayerConfigError::Io { path, source })?;
+    Ok(relative_path)
+}
+
+fn path_str(path: &Path) -> anyhow::Result<&str> {
+    path.to_str()
+        .with_context(|| format!("relayer path {} is not valid UTF-8", path.display()))
+}
+
+/// Check that a key file exists, parses and belongs to `expected_account_id`.
+fn validate_key_file(
+    app_dir: &Path,
+    relative_path: &Path,
+    expected_account_id: &AccountId,
+) -> Result<(), RelayerConfigError> {
+    let path = app_dir.join(relative_path);
+    if !path.is_file() {
+        return Err(RelayerConfigError::MissingKeyFile { path });
+    }
+    let invalid = |reason: String| RelayerConfigError::InvalidKeyFile {
+        path: path.clone(),
+        reason,
+    };
+
+    let contents = std::fs::read(&path).map_err(|source| RelayerConfigError::Io {
+        path: path.clone(),
+        source,
+    })?;
+    let key_file: serde_json::Value =
+        serde_json::from_slice(&contents).map_err(|err| invalid(err.to_string()))?;
+    let account_id = key_file["account_id"]
+        .as_str()
+        .ok_or_else(|| invalid("missing account_id".to_string()))?;
+    if account_id != expected_account_id.as_str() {
+        return Err(RelayerConfigError::KeyFileAccountMismatch {
+            path,
+            expected: expected_account_id.clone(),
+            found: account_id.to_string(),
         });
-        let mut file = NamedTempFile::new_in(dir).context("creating temporary key file")?;
-        file.write_all(&serde_json::to_vec(&key_file_json).context("serializing key file")?)
-            .context("writing key file")?;
-        Ok(Self { file })
     }
+    let private_key: SecretKey = key_file["private_key"]
+        .as_str()
+        .ok_or_else(|| invalid("missing private_key".to_string()))?
+        .parse()
+        .map_err(|err| invalid(format!("unparsable private_key: {err}")))?;
+    if key_file["public_key"].as_str() != Some(private_key.public_key().to_string().as_str()) {
+        return Err(invalid("public_key does not match private_key".to_string()));
+    }
+    Ok(())
 }
 
+/// Relayer policy knobs passed into the relayer's `config.toml`.
+///
+/// `None` fields are left out of the config so that the relayer's own defaults apply.
+#[derive(Clone, Debug, Default)]
+pub struct RelayerPolicy {
+    /// Contracts the relayer pays for, in addition to the creator account.
+    pub whitelisted_contracts: Vec<AccountId>,
+    /// Delegate action receivers the relayer accepts, in addition to the creator account.
+    pub whitelisted_delegate_action_receiver_ids: Vec<AccountId>,
+    pub use_whitelisted_delegate_action_receiver_ids: Option<bool>,
+    pub use_redis: Option<bool>,
+    pub use_fastauth_features: Option<bool>,
+    pub use_shared_storage: Option<bool>,
+    pub use_pay_with_ft: Option<bool>,
+    pub burn_address: Option<AccountId>,
+    pub flametrace_performance: Option<bool>,
+    /// Gas allowances seeded into the relayer's Redis, per account.
+    pub allowances: Vec<(AccountId, u64)>,
+}
+
+#[derive(Clone, Debug)]
 pub struct RelayerConfig {
     pub ip_address: [u8; 4],
     pub port: u16,
@@ -48,12 +175,64 @@ pub struct RelayerConfig {
     pub wallet_url: String,
     pub explorer_transaction_url: String,
     pub rpc_api_key: String,
+    pub use_whitelisted_delegate_action_receiver_ids: Option<bool>,
+    pub use_redis: Option<bool>,
+    pub use_fastauth_features: Option<bool>,
+    pub use_shared_storage: Option<bool>,
+    pub use_pay_with_ft: Option<bool>,
+    pub burn_address: Option<AccountId>,
+    pub flametrace_performance: Option<bool>,
 }
 
 impl RelayerConfig {
-    fn to_string(self) -> String {
-        let mut config_table = Value::Table(toml::value::Table::new());
-        let table = config_table.as_table_mut().unwrap();
+    /// Apply whitelists and feature flags from `policy`. `always_whitelisted` (the creator
+    /// account) stays whitelisted regardless of the policy.
+    pub fn apply_policy(&mut self, policy: &RelayerPolicy, always_whitelisted: &AccountId) {
+        self.whitelisted_contracts = std::iter::once(always_whitelisted)
+            .chain(&policy.whitelisted_contracts)
+            .cloned()
+            .collect();
+        self.whitelisted_delegate_action_receiver_ids = std::iter::once(always_whitelisted)
+            .chain(&policy.whitelisted_delegate_action_receiver_ids)
+            .cloned()
+            .collect();
+        self.use_whitelisted_delegate_action_receiver_ids =
+            policy.use_whitelisted_delegate_action_receiver_ids;
+        self.use_redis = policy.use_redis;
+        self.use_fastauth_features = policy.use_fastauth_features;
+        self.use_shared_storage = policy.use_shared_storage;
+        self.use_pay_with_ft = policy.use_pay_with_ft;
+        self.burn_address = policy.burn_address.clone();
+        self.flametrace_performance = policy.flametrace_performance;
+    }
+
+    /// Check that every key file referenced by the config exists under `app_dir` (the host
+    /// directory mounted as the relayer's working directory) and matches its account.
+    pub fn validate(&self, app_dir: &Path) -> Result<(), RelayerConfigError> {
+        if self.keys_filenames.is_empty() {
+            return Err(RelayerConfigError::NoRelayerKeys);
+        }
+        for keys_filename in &self.keys_filenames {
+            validate_key_file(app_dir, keys_filename, &self.relayer_account_id)?;
+        }
+        validate_key_file(
+            app_dir,
+            Path::new(&self.shared_storage_keys_filename),
+            &self.shared_storage_account_id,
+        )
+    }
+
+    fn write_to(&self, path: &Path) -> anyhow::Result<()> {
+        let contents = self.clone().to_toml()?;
+        std::fs::write(path, contents).map_err(|source| RelayerConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(())
+    }
+
+    fn to_toml(self) -> anyhow::Result<String> {
+        let mut table = toml::value::Table::new();
 
         table.insert(
             "ip_address".to_string(),
@@ -74,9 +253,9 @@ impl RelayerConfig {
             "keys_filenames".to_string(),
             Value::Array(
                 self.keys_filenames
-                    .into_iter()
-                    .map(|filename| Value::String(filename.to_str().unwrap().to_string()))
-                    .collect(),
+                    .iter()
+                    .map(|filename| Ok(Value::String(path_str(filename)?.to_string())))
+                    .collect::<anyhow::Result<_>>()?,
             ),
         );
 
@@ -122,16 +301,41 @@ impl RelayerConfig {
         ); // not used
         table.insert("rpc_api_key".to_string(), Value::String(self.rpc_api_key)); // not used
 
-        toml::to_string(&config_table).expect("failed to serialize relayer config")
+        let flags = [
+            (
+                "use_whitelisted_delegate_action_receiver_ids",
+                self.use_whitelisted_delegate_action_receiver_ids,
+            ),
+            ("use_redis", self.use_redis),
+            ("use_fastauth_features", self.use_fastauth_features),
+            ("use_shared_storage", self.use_shared_storage),
+            ("use_pay_with_ft", self.use_pay_with_ft),
+            ("flametrace_performance", self.flametrace_performance),
+        ];
+        for (name, flag) in flags {
+            if let Some(flag) = flag {
+                table.insert(name.to_string(), Value::Boolean(flag));
+            }
+        }
+        if let Some(burn_address) = self.burn_address {
+            table.insert(
+                "burn_address".to_string(),
+                Value::String(burn_address.to_string()),
+            );
+        }
+
+        toml::to_string(&Value::Table(table)).context("serializing relayer config")
     }
 }
 
 pub struct Relayer<'a> {
     pub container: Container<'a, GenericImage>,
     pub http_address: String,
-    // Keep key file handles to ensure that tmp files outlive the container.
-    _social_account_key_file: KeyFile,
-    _relayer_keyfiles: Vec<KeyFile>,
+    pub config: RelayerConfig,
+    pub policy: RelayerPolicy,
+    network: String,
+    // Keep the config directory to ensure that config and key files outlive the container.
+    config_dir: TempDir,
 }
 
 impl<'a> Relayer<'a> {
@@ -148,6 +352,7 @@ impl<'a> Relayer<'a> {
         social_db_contract_id: &AccountId,
         social_account_id: &AccountId,
         social_account_sk: &near_workspaces::types::SecretKey,
+        policy: &RelayerPolicy,
     ) -> anyhow::Result<Relayer<'a>> {
         tracing::info!(
             network,
@@ -160,68 +365,82 @@ impl<'a> Relayer<'a> {
             "running relayer container"
         );
 
-        // Create tmp folder to store relayer configs
+        // Layout mirrors the relayer's working directory: `config.toml` and `account_keys/`.
         let relayer_config_dir =
             tempfile::tempdir().context("creating relayer config directory")?;
-
-        // Create dir for keys
-        let key_dir =
-            tempfile::tempdir_in(&relayer_config_dir).context("creating relayer keys directory")?;
+        let key_dir = relayer_config_dir.path().join(KEYS_DIR);
+        std::fs::create_dir(&key_dir).map_err(|source| RelayerConfigError::Io {
+            path: key_dir.clone(),
+            source,
+        })?;
 
         // Create JSON key files
-        let social_account_key_file =
-            KeyFile::temp_in_dir(social_account_id, social_account_sk, &key_dir)?;
-        let mut relayer_keyfiles = Vec::with_capacity(relayer_account_sks.len());
-        for relayer_sk in relayer_account_sks {
-            relayer_keyfiles.push(KeyFile::temp_in_dir(
+        let shared_storage_keys_filename = write_key_file(
+            relayer_config_dir.path(),
+            &format!("{}.json", social_account_id),
+            social_account_id,
+            social_account_sk,
+        )?;
+        let mut keys_filenames = Vec::with_capacity(relayer_account_sks.len());
+        for (index, relayer_sk) in relayer_account_sks.iter().enumerate() {
+            keys_filenames.push(write_key_file(
+                relayer_config_dir.path(),
+                &format!("{}.{}.json", relayer_account_id, index),
                 relayer_account_id,
                 relayer_sk,
-                &key_dir,
             )?);
         }
 
         // Create relayer config file
-        let relayer_config = RelayerConfig {
+        let mut relayer_config = RelayerConfig {
             ip_address: [0, 0, 0, 0],
             port: Self::CONTAINER_PORT,
             relayer_account_id: relayer_account_id.clone(),
-            keys_filenames: relayer_keyfiles
-                .iter()
-                .map(|kf| {
-                    kf.file
-                        .path()
-                        .strip_prefix(&relayer_config_dir)
-                        .unwrap()
-                        .to_path_buf()
-                })
-                .collect(),
+            keys_filenames,
             shared_storage_account_id: social_account_id.clone(),
-            shared_storage_keys_filename: format!("./account_keys/{}.json", social_account_id),
-            whitelisted_contracts: vec![creator_account_id.clone()],
-            whitelisted_delegate_action_receiver_ids: vec![creator_account_id.clone()],
+            shared_storage_keys_filename: path_str(&shared_storage_keys_filename)?.to_string(),
+            whitelisted_contracts: Vec::new(),
+            whitelisted_delegate_action_receiver_ids: Vec::new(),
             redis_url: redis_url.to_string(),
             social_db_contract_id: social_db_contract_id.clone(),
             rpc_url: near_rpc.to_string(),
             wallet_url: "https://wallet.testnet.near.org".to_string(),
             explorer_transaction_url: "https://explorer.testnet.near.org/transactions/".to_string(),
             rpc_api_key: "".to_string(),
+            use_whitelisted_delegate_action_receiver_ids: None,
+            use_redis: None,
+            use_fastauth_features: None,
+            use_shared_storage: None,
+            use_pay_with_ft: None,
+            burn_address: None,
+            flametrace_performance: None,
         };
+        relayer_config.apply_policy(policy, creator_account_id);
+        relayer_config.validate(relayer_config_dir.path())?;
         let relayer_config_path = relayer_config_dir.path().join("config.toml");
-        let mut relayer_config_file = File::create(&relayer_config_path).unwrap_or_else(|_| {
-            panic!(
-                "failed to create relayer config file at {}",
-                relayer_config_path.display()
-            )
-        });
-        relayer_config_file
-            .write_all(relayer_config.to_string().as_bytes())
-            .unwrap_or_else(|_| {
-                panic!(
-                    "failed to write relayer config to {}",
-                    relayer_config_path.display()
-                )
-            });
+        relayer_config.write_to(&relayer_config_path)?;
 
+        let image = Self::image(relayer_config_dir.path(), network)?;
+        let container = docker_client.cli.run(image);
+
+        let ip_address = docker_client
+            .get_network_ip_address(&container, network)
+            .await?;
+        let http_address = format!("http://{}:{}", ip_address, Self::CONTAINER_PORT);
+        tracing::info!(http_address, "Relayer container is running");
+
+        Ok(Relayer {
+            container,
+            http_address,
+            config: relayer_config,
+            policy: policy.clone(),
+            network: network.to_string(),
+            config_dir: relayer_config_dir,
+        })
+    }
+
+    /// Relayer image reading `config.toml` and `account_keys/` from `config_dir`.
+    fn image(config_dir: &Path, network: &str) -> anyhow::Result<RunnableImage<GenericImage>> {
         let image = GenericImage::new(
             "ghcr.io/near/os-relayer",
             "12ba6e35690df3979fce0b36a41d0ca0db9c0ab4",
@@ -229,31 +448,47 @@ impl<'a> Relayer<'a> {
         .with_wait_for(WaitFor::message_on_stdout("listening on"))
         .with_exposed_port(Self::CONTAINER_PORT)
         .with_volume(
-            relayer_config_path.to_str().unwrap(),
+            path_str(&config_dir.join("config.toml"))?,
             "/relayer-app/config.toml",
         )
         .with_volume(
-            key_dir.path().to_str().unwrap(),
-            "/relayer-app/account_keys", // FIXME: directory name is probably going to be mangled, so it wouldn't work like that
+            path_str(&config_dir.join(KEYS_DIR))?,
+            "/relayer-app/account_keys",
         )
         .with_env_var("RUST_LOG", "DEBUG");
 
         let image: RunnableImage<GenericImage> = image.into();
-        let image = image.with_network(network);
-        let container = docker_client.cli.run(image);
+        Ok(image.with_network(network))
+    }
+
+    /// Regenerate the relayer config with a new policy and replace the container to pick it up.
+    /// The new container keeps the host port, so host clients and the reverse proxy keep working.
+    pub async fn update_policy(
+        &mut self,
+        docker_client: &'a DockerClient,
+        policy: &RelayerPolicy,
+        creator_account_id: &AccountId,
+    ) -> anyhow::Result<()> {
+        tracing::info!(?policy, "updating relayer policy");
+        self.config.apply_policy(policy, creator_account_id);
+        self.policy = policy.clone();
+        self.config.validate(self.config_dir.path())?;
+        self.config
+            .write_to(&self.config_dir.path().join("config.toml"))?;
+
+        let host_port = self.host_relayer_port_ipv4();
+        self.container.stop();
+        let image = Self::image(self.config_dir.path(), &self.network)?
+            .with_mapped_port((host_port, Self::CONTAINER_PORT));
+        // Replacing the stopped container removes it.
+        self.container = docker_client.cli.run(image);
 
         let ip_address = docker_client
-            .get_network_ip_address(&container, network)
+            .get_network_ip_address(&self.container, &self.network)
             .await?;
-        let http_address = format!("http://{}:{}", ip_address, Self::CONTAINER_PORT);
-        tracing::info!(http_address, "Relayer container is running");
-
-        Ok(Relayer {
-            container,
-            http_address,
-            _social_account_key_file: social_account_key_file,
-            _relayer_keyfiles: relayer_keyfiles,
-        })
+        self.http_address = format!("http://{}:{}", ip_address, Self::CONTAINER_PORT);
+        tracing::info!(http_address = self.http_address, "Relayer container replaced");
+        Ok(())
     }
 
     pub fn host_relayer_port_ipv4(&self) -> u16 {
@@ -1,3 +1,4 @@
+use crate::lake::{FAKE_ACCESS_KEY_ID, FAKE_SECRET_ACCESS_KEY};
 use crate::DockerClient;
 use testcontainers::{Container, GenericImage, RunnableImage};
 
@@ -22,8 +23,8 @@ impl<'a> Runner<'a> {
         tracing::info!(network, "starting QueryAPI Runner container");
 
         let image = GenericImage::new("darunrs/queryapi", "runner")
-            .with_env_var("AWS_ACCESS_KEY_ID", "FAKE_LOCALSTACK_KEY_ID")
-            .with_env_var("AWS_SECRET_ACCESS_KEY", "FAKE_LOCALSTACK_ACCESS_KEY")
+            .with_env_var("AWS_ACCESS_KEY_ID", FAKE_ACCESS_KEY_ID)
+            .with_env_var("AWS_SECRET_ACCESS_KEY", FAKE_SECRET_ACCESS_KEY)
             .with_env_var("AWS_REGION", region)
             .with_env_var("REGION", region)
             .with_env_var("REDIS_CONNECTION_STRING", redis_address)
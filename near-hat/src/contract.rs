@@ -0,0 +1,57 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+/// Build a near-sdk crate to wasm and return the contract bytes.
+///
+/// `manifest_path` may point at the crate's `Cargo.toml` or at the directory containing it.
+pub fn build_contract(manifest_path: &Path) -> anyhow::Result<Vec<u8>> {
+    let manifest_path = if manifest_path.is_dir() {
+        manifest_path.join("Cargo.toml")
+    } else {
+        manifest_path.to_path_buf()
+    };
+    let _span = tracing::info_span!("building contract");
+    tracing::info!(manifest_path = %manifest_path.display(), "building contract");
+
+    let output = Command::new("cargo")
+        .arg("build")
+        .arg("--target")
+        .arg("wasm32-unknown-unknown")
+        .arg("--release")
+        .arg("--message-format=json")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .stderr(Stdio::inherit())
+        .output()
+        .context("running cargo build")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "cargo build failed for {} ({})",
+            manifest_path.display(),
+            output.status
+        ));
+    }
+
+    // The last wasm artifact reported by cargo belongs to the crate itself, dependencies come first.
+    let wasm_path = output
+        .stdout
+        .split(|byte| *byte == b'\n')
+        .filter_map(|line| serde_json::from_slice::<Value>(line).ok())
+        .filter(|message| message["reason"] == "compiler-artifact")
+        .flat_map(|message| match &message["filenames"] {
+            Value::Array(filenames) => filenames
+                .iter()
+                .filter_map(|filename| filename.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        })
+        .filter(|filename| filename.ends_with(".wasm"))
+        .last()
+        .ok_or_else(|| anyhow!("cargo build of {} produced no wasm artifact", manifest_path.display()))?;
+
+    tracing::info!(wasm_path, "contract built");
+    std::fs::read(&wasm_path).with_context(|| format!("reading wasm artifact {wasm_path}"))
+}
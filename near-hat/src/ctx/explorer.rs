@@ -1,15 +1,57 @@
 use super::lake_indexer::LakeIndexerCtx;
 use crate::client::DockerClient;
-use crate::containers::explorer_backend::ExplorerBackend;
+use crate::containers::explorer_backend::{ExplorerBackend, ExplorerDbConfig};
 use crate::containers::explorer_database::ExplorerDatabase;
 use crate::containers::explorer_frontend::ExplorerFrontend;
 use crate::containers::explorer_indexer::ExplorerIndexer;
+use crate::explorer_links::{ExplorerLinks, EXPLORER_PROXY_URL};
+use crate::wait::{poll_until, WAIT_TIMEOUT};
+use anyhow::Context;
+use near_workspaces::result::{ExecutionFinalResult, ExecutionSuccess};
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Explorer stack settings. By default everything runs locally, without external services.
+#[derive(Clone, Debug)]
+pub struct ExplorerConfig {
+    /// Start the explorer at all.
+    pub enabled: bool,
+    /// Start the backend and frontend. Without them blocks are only indexed into the database.
+    pub ui: bool,
+    /// Analytics database, defaults to an empty stand-in in the explorer database container.
+    pub analytics_db: Option<ExplorerDbConfig>,
+    /// Telemetry database, defaults to an empty stand-in in the explorer database container.
+    pub telemetry_db: Option<ExplorerDbConfig>,
+    /// Segment key the frontend reports page views with, empty to disable tracking.
+    pub segment_write_key: String,
+    /// Explorer URL the frontend links to.
+    pub explorer_link: String,
+    /// Prefix of the frontend's account profile links.
+    pub wallet_profile_prefix: String,
+}
+
+impl Default for ExplorerConfig {
+    fn default() -> Self {
+        ExplorerConfig {
+            enabled: true,
+            ui: true,
+            analytics_db: None,
+            telemetry_db: None,
+            segment_write_key: String::new(),
+            explorer_link: EXPLORER_PROXY_URL.to_string(),
+            wallet_profile_prefix: format!("{EXPLORER_PROXY_URL}/accounts"),
+        }
+    }
+}
 
 pub struct ExplorerCtx<'a> {
     pub indexer: ExplorerIndexer<'a>,
     pub database: ExplorerDatabase<'a>,
-    pub backend: ExplorerBackend<'a>,
-    pub frontend: ExplorerFrontend<'a>,
+    pub backend: Option<ExplorerBackend<'a>>,
+    pub frontend: Option<ExplorerFrontend<'a>>,
+    /// Links through the reverse proxy, or [`ExplorerConfig::explorer_link`] if overridden.
+    pub links: ExplorerLinks,
+    docker_client: &'a DockerClient,
 }
 
 impl<'a> ExplorerCtx<'a> {
@@ -17,24 +59,45 @@ impl<'a> ExplorerCtx<'a> {
         docker_client: &'a DockerClient,
         network: &str,
         lake_indexer_ctx: &LakeIndexerCtx<'a>,
+        config: &ExplorerConfig,
     ) -> anyhow::Result<ExplorerCtx<'a>> {
         let database = ExplorerDatabase::run(docker_client, network).await?;
 
         let indexer = ExplorerIndexer::run(
             docker_client,
             network,
-            &lake_indexer_ctx.localstack.s3_address,
-            &lake_indexer_ctx.localstack.s3_bucket,
-            &lake_indexer_ctx.localstack.s3_region,
+            lake_indexer_ctx.storage.s3_address(),
+            lake_indexer_ctx.storage.s3_bucket(),
+            lake_indexer_ctx.storage.s3_region(),
             &database.connection_string,
         )
         .await?;
 
+        if !config.ui {
+            return Ok(ExplorerCtx {
+                indexer,
+                database,
+                backend: None,
+                frontend: None,
+                links: ExplorerLinks::new(&config.explorer_link),
+                docker_client,
+            });
+        }
+
+        let analytics_db = config
+            .analytics_db
+            .clone()
+            .unwrap_or_else(|| database.db_config(ExplorerDatabase::ANALYTICS_DATABASE));
+        let telemetry_db = config
+            .telemetry_db
+            .clone()
+            .unwrap_or_else(|| database.db_config(ExplorerDatabase::TELEMETRY_DATABASE));
         let backend = ExplorerBackend::run(
             docker_client,
             network,
-            &database.host,
-            database.port,
+            &database.db_config("postgres"),
+            &analytics_db,
+            &telemetry_db,
             &lake_indexer_ctx.lake_indexer.rpc_address,
         )
         .await?;
@@ -46,14 +109,109 @@ impl<'a> ExplorerCtx<'a> {
             backend.host_port_ipv4(),
             &backend.ip_address,
             backend.port,
+            &config.segment_write_key,
+            &config.explorer_link,
+            &config.wallet_profile_prefix,
         )
         .await?;
 
         Ok(ExplorerCtx {
             indexer,
             database,
-            backend,
-            frontend,
+            backend: Some(backend),
+            frontend: Some(frontend),
+            links: ExplorerLinks::new(&config.explorer_link),
+            docker_client,
+        })
+    }
+
+    pub fn tx_url(&self, transaction_hash: impl Display) -> String {
+        self.links.tx_url(transaction_hash)
+    }
+
+    pub fn account_url(&self, account_id: impl Display) -> String {
+        self.links.account_url(account_id)
+    }
+
+    pub fn block_url(&self, height: u64) -> String {
+        self.links.block_url(height)
+    }
+
+    /// Fail with a link to the transaction in the explorer, see [`ExplorerLinks::expect_success`].
+    pub fn expect_success(&self, result: ExecutionFinalResult) -> anyhow::Result<ExecutionSuccess> {
+        self.links.expect_success(result)
+    }
+
+    /// Links to the frontend's host port, for when the reverse proxy is not running.
+    pub fn host_links(&self) -> Option<ExplorerLinks> {
+        self.frontend
+            .as_ref()
+            .map(|frontend| ExplorerLinks::new(&frontend.host_address_ipv4()))
+    }
+
+    /// Highest block height stored in the explorer database, `None` while it has no blocks.
+    pub async fn latest_block_height(&self) -> anyhow::Result<Option<u64>> {
+        let output = self
+            .database
+            .query(self.docker_client, "SELECT MAX(block_height) FROM blocks")
+            .await?;
+        // psql prints NULL as an empty string.
+        if output.is_empty() {
+            return Ok(None);
+        }
+        let height = output
+            .parse()
+            .with_context(|| format!("unexpected latest block height '{}'", output))?;
+        Ok(Some(height))
+    }
+
+    /// Wait until the explorer indexer has stored block `height`.
+    pub async fn wait_for_block(&self, height: u64) -> anyhow::Result<()> {
+        self.wait_for_block_with_timeout(height, WAIT_TIMEOUT).await
+    }
+
+    /// Same as [`ExplorerCtx::wait_for_block`], failing after `timeout`.
+    pub async fn wait_for_block_with_timeout(&self, height: u64, timeout: Duration) -> anyhow::Result<()> {
+        let what = format!("explorer to reach block {}", height);
+        poll_until(&what, timeout, || async {
+            Ok(self
+                .latest_block_height()
+                .await?
+                .map_or(false, |latest| latest >= height))
+        })
+        .await
+    }
+
+    /// Wait until a transaction is visible in the explorer database.
+    pub async fn wait_for_transaction(&self, transaction_hash: &str) -> anyhow::Result<()> {
+        self.wait_for_transaction_with_timeout(transaction_hash, WAIT_TIMEOUT)
+            .await
+    }
+
+    /// Same as [`ExplorerCtx::wait_for_transaction`], failing after `timeout`.
+    pub async fn wait_for_transaction_with_timeout(
+        &self,
+        transaction_hash: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        // Transaction hashes are base58, so they can be inlined into the query as is.
+        anyhow::ensure!(
+            transaction_hash.chars().all(|c| c.is_ascii_alphanumeric()),
+            "invalid transaction hash '{}'",
+            transaction_hash
+        );
+        let sql = format!(
+            "SELECT COUNT(*) FROM transactions WHERE transaction_hash = '{}'",
+            transaction_hash
+        );
+        let what = format!("explorer to index transaction {}", transaction_hash);
+        poll_until(&what, timeout, || async {
+            let output = self.database.query(self.docker_client, &sql).await?;
+            let count: u64 = output
+                .parse()
+                .with_context(|| format!("unexpected transaction count '{}'", output))?;
+            Ok(count > 0)
         })
+        .await
     }
 }
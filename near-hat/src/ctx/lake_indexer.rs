@@ -1,16 +1,25 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use crate::client::DockerClient;
-use crate::containers::lake_indexer::LakeIndexer;
-use crate::containers::localstack::LocalStack;
+use crate::containers::lake_indexer::{LakeIndexer, LakeIndexerOptions};
+use crate::containers::lake_storage::{LakeStorage, LakeStorageKind};
+use crate::events::{assert_event_emitted, events_in_block, events_in_blocks, EmittedEvent, EventFilter};
+use crate::lake::{BlockStream, LakeS3Config};
+use crate::lake_bucket::LakeBucket;
+use crate::lake_harness::LakeHarness;
+use crate::tx_tracer::{TxTrace, TxTracer};
+use near_lake_primitives::block::Block;
+use std::future::Future;
+use std::time::Duration;
 use crate::validator::ValidatorContainer;
+use crate::wait::{poll_until, TimedOut, WAIT_TIMEOUT};
 use near_workspaces::network::{Sandbox, ValidatorKey};
 use near_workspaces::Worker;
 use serde_json::{json, Value};
 
 pub struct LakeIndexerCtx<'a> {
-    pub localstack: LocalStack<'a>,
+    pub storage: LakeStorage<'a>,
     pub lake_indexer: LakeIndexer<'a>,
     // FIXME: Technically this network is not sandbox, but workspaces does not support plain localnet
     pub worker: Worker<Sandbox>,
@@ -20,19 +29,28 @@ impl<'a> LakeIndexerCtx<'a> {
     pub async fn new(
         docker_client: &'a DockerClient,
         network: &str,
+        storage_kind: LakeStorageKind,
+        lake_indexer_options: &LakeIndexerOptions,
         key_json_ref: Rc<RefCell<Value>>,
     ) -> anyhow::Result<LakeIndexerCtx<'a>> {
         let s3_bucket = "localnet".to_string();
         let s3_region = "us-east-1".to_string();
-        let localstack =
-            LocalStack::run(docker_client, network, s3_bucket.clone(), s3_region.clone()).await?;
+        let storage = LakeStorage::run(
+            docker_client,
+            network,
+            storage_kind,
+            s3_bucket.clone(),
+            s3_region.clone(),
+        )
+        .await?;
 
         let lake_indexer = LakeIndexer::run(
             docker_client,
             network,
-            &localstack.s3_address,
+            storage.s3_address(),
             s3_bucket,
             s3_region,
+            lake_indexer_options,
         )
         .await?;
 
@@ -50,9 +68,84 @@ impl<'a> LakeIndexerCtx<'a> {
         key_json_ref.borrow_mut()[validator_key.account_id.to_string()] = json!(validator_key.secret_key.to_string());
 
         Ok(LakeIndexerCtx {
-            localstack,
+            storage,
             lake_indexer,
             worker
         })
     }
+
+    /// Lake bucket location, reachable from the host.
+    pub fn lake_s3_config(&self) -> LakeS3Config {
+        self.storage.lake_s3_config()
+    }
+
+    pub fn bucket(&self) -> LakeBucket {
+        self.storage.bucket()
+    }
+
+    /// Stream blocks written by the Lake indexer, starting at `from_height`.
+    pub fn stream_blocks(&self, from_height: u64) -> anyhow::Result<BlockStream> {
+        self.lake_s3_config().stream_blocks(from_height)
+    }
+
+    /// Run a user indexer handler against the local Lake bucket, see [`LakeHarness`].
+    pub fn harness<F, Fut>(&self, from_height: u64, handler: F) -> anyhow::Result<LakeHarness>
+    where
+        F: FnMut(Block) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        LakeHarness::start(&self.lake_s3_config(), from_height, handler)
+    }
+
+    /// Trace a recent transaction through the receipts it produced, see [`TxTracer`].
+    pub async fn trace_transaction(&self, transaction_hash: &str) -> anyhow::Result<TxTrace> {
+        TxTracer::new(self.bucket()).trace(transaction_hash, None).await
+    }
+
+    /// NEP-297 events matching `filter` emitted from `from_height` up to the latest block.
+    pub async fn events(&self, from_height: u64, filter: &EventFilter) -> anyhow::Result<Vec<EmittedEvent>> {
+        let events = events_in_blocks(&self.bucket(), from_height, u64::MAX).await?;
+        Ok(filter.apply(events))
+    }
+
+    /// Wait until an event matching `filter` is emitted at or after `from_height`.
+    pub async fn assert_event_emitted(&self, from_height: u64, filter: &EventFilter) -> anyhow::Result<EmittedEvent> {
+        self.assert_event_emitted_with_timeout(from_height, filter, WAIT_TIMEOUT)
+            .await
+    }
+
+    /// Same as [`LakeIndexerCtx::assert_event_emitted`], giving up after `timeout`.
+    pub async fn assert_event_emitted_with_timeout(
+        &self,
+        from_height: u64,
+        filter: &EventFilter,
+        timeout: Duration,
+    ) -> anyhow::Result<EmittedEvent> {
+        let bucket = self.bucket();
+        let what = format!("event matching {:?}", filter);
+        // Every poll only scans the blocks uploaded since the previous one.
+        let events = RefCell::new(Vec::new());
+        let next_height = Cell::new(from_height);
+        let waited = poll_until(&what, timeout, || async {
+            for height in bucket.heights(Some(next_height.get()), None).await? {
+                let block_events = events_in_block(&bucket, height).await?;
+                events.borrow_mut().extend(block_events);
+                next_height.set(height + 1);
+            }
+            Ok(events.borrow().iter().any(|event| filter.matches(event)))
+        })
+        .await;
+        match waited {
+            // Report every emitted event on timeout rather than a bare timeout.
+            Err(err) if err.is::<TimedOut>() => tracing::warn!(%err, "event was not emitted in time"),
+            waited => waited?,
+        }
+        assert_event_emitted(&events.into_inner(), filter)
+    }
+
+    /// Restart the node and Lake indexer, keeping chain state. The host RPC port stays the same,
+    /// so [`LakeIndexerCtx::worker`] keeps working, see [`LakeIndexer::restart`].
+    pub async fn restart_lake_indexer(&mut self, docker_client: &'a DockerClient) -> anyhow::Result<()> {
+        self.lake_indexer.restart(docker_client).await
+    }
 }
@@ -3,3 +3,4 @@ pub mod lake_indexer;
 pub mod nearcore;
 pub mod relayer;
 pub mod queryapi;
+pub mod sandbox;
@@ -1,66 +1,105 @@
-use near_workspaces::network::Sandbox;
-use near_workspaces::types::{NearToken, SecretKey};
-use near_workspaces::{AccessKey, Account, Contract, Worker};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::catalog::StandardContract;
+use crate::state::AccountDump;
+use anyhow::{anyhow, Context};
+use serde_json::{json, Value};
+use near_workspaces::network::{Sandbox, ValidatorKey};
+use near_workspaces::types::{KeyType, NearToken, PublicKey, SecretKey};
+use near_workspaces::{AccessKey, Account, AccountDetails, AccountDetailsPatch, AccountId, Contract, CryptoHash, Worker};
 
 pub struct NearcoreCtx {
     pub(crate) worker: Worker<Sandbox>,
 }
 
 impl NearcoreCtx {
-    async fn initialize_social_db(worker: &Worker<Sandbox>) -> anyhow::Result<Contract> {
-        let _span = tracing::info_span!("initializing socialdb contract");
-        let social_db = worker
-            .import_contract(&"social.near".parse()?, &near_workspaces::mainnet().await?)
-            .transact()
-            .await?;
-        social_db
-            .call("new")
-            .max_gas()
-            .transact()
-            .await?
-            .into_result()?;
+    pub async fn new(worker: &Worker<Sandbox>) -> anyhow::Result<NearcoreCtx> {
+        Ok(NearcoreCtx {
+            worker: worker.clone(),
+        })
+    }
 
-        Ok(social_db)
+    /// Deploy vendored standard contracts to their well-known account ids and initialize them.
+    ///
+    /// Accounts are created through state patching, so top-level ids like `near` or
+    /// `social.near` work without a registrar and without network access.
+    pub async fn deploy_standard_contracts(
+        &self,
+        contracts: &[StandardContract],
+        key_json_ref: Rc<RefCell<Value>>,
+    ) -> anyhow::Result<Vec<Contract>> {
+        let mut deployed = Vec::with_capacity(contracts.len());
+        for standard_contract in contracts {
+            let _span = tracing::info_span!("initializing standard contract");
+            let wasm = standard_contract.wasm()?;
+            let account_id: AccountId = standard_contract.account_id().parse()?;
+            let secret_key = SecretKey::from_random(KeyType::ED25519);
+            self.worker
+                .patch(&account_id)
+                .account(AccountDetailsPatch::default().balance(NearToken::from_near(1000)))
+                .access_key(secret_key.public_key(), AccessKey::full_access())
+                .code(&wasm)
+                .transact()
+                .await?;
+
+            let contract = Contract::from_secret_key(account_id.clone(), secret_key.clone(), &self.worker);
+            for (method, args) in standard_contract.init_calls() {
+                contract
+                    .call(method)
+                    .args_json(args)
+                    .max_gas()
+                    .transact()
+                    .await?
+                    .into_result()?;
+            }
+
+            key_json_ref.borrow_mut()[account_id.to_string()] = json!(secret_key.to_string());
+            tracing::info!(%account_id, contract = %standard_contract, "standard contract initialized");
+            deployed.push(contract);
+        }
+
+        Ok(deployed)
     }
 
-    // Linkdrop contains top-level account creation logic
-    async fn initialize_linkdrop(worker: &Worker<Sandbox>) -> anyhow::Result<()> {
-        let _span = tracing::info_span!("initializing linkdrop contract");
-        let near_root_account = worker.root_account()?;
-        worker
-            .import_contract(&"near".parse()?, &near_workspaces::mainnet().await?)
-            .dest_account_id(near_root_account.id())
-            .transact()
+    /// Connect to an already running NEARHat network (e.g. one started by `near-hat-cli start`).
+    pub async fn connect(
+        rpc_address: &str,
+        validator_account_id: AccountId,
+        validator_sk: SecretKey,
+    ) -> anyhow::Result<NearcoreCtx> {
+        let worker = near_workspaces::sandbox()
+            .rpc_addr(rpc_address)
+            .validator_key(ValidatorKey::Known(validator_account_id, validator_sk))
             .await?;
-        near_root_account
-            .call(near_root_account.id(), "new")
-            .max_gas()
-            .transact()
-            .await?
-            .into_result()?;
 
-        Ok(())
+        Ok(NearcoreCtx { worker })
     }
 
-    pub async fn new(worker: &Worker<Sandbox>) -> anyhow::Result<NearcoreCtx> {
-        // Self::initialize_linkdrop(worker).await?;
-        // TODO: move out of nearcore trait into its own ctx
-        // let social_db = Self::initialize_social_db(worker).await?;
-
-        Ok(NearcoreCtx {
-            worker: worker.clone(),
-        })
+    /// Account handle for an existing account whose key is known.
+    pub fn account(&self, account_id: AccountId, secret_key: SecretKey) -> Account {
+        Account::from_secret_key(account_id, secret_key, &self.worker)
     }
 
+    /// Create `<prefix>.<root account>`. Only direct subaccounts of the root account can be
+    /// created, so `prefix` must not contain dots.
     pub async fn create_account(
         &self,
         prefix: &str,
         initial_balance: NearToken,
     ) -> anyhow::Result<Account> {
         let _span = tracing::info_span!("creating account with random account id");
-        let new_account = self
-            .worker
-            .root_account()?
+        let root_account = self.worker.root_account()?;
+        if prefix.contains('.') {
+            return Err(anyhow!(
+                "cannot create '{}.{}': only direct subaccounts of '{}' can be created",
+                prefix,
+                root_account.id(),
+                root_account.id()
+            ));
+        }
+        let new_account = root_account
             .create_subaccount(prefix)
             .initial_balance(initial_balance)
             .transact()
@@ -71,6 +110,56 @@ impl NearcoreCtx {
         Ok(new_account)
     }
 
+    /// Reuse an account we hold a key for, or create it as a subaccount of the root account.
+    pub async fn get_or_create_account(
+        &self,
+        account_id: &AccountId,
+        secret_key: Option<SecretKey>,
+        initial_balance: NearToken,
+    ) -> anyhow::Result<Account> {
+        if let Some(secret_key) = secret_key {
+            return Ok(Account::from_secret_key(account_id.clone(), secret_key, &self.worker));
+        }
+
+        let root_account = self.worker.root_account()?;
+        let prefix = account_id
+            .as_str()
+            .strip_suffix(&format!(".{}", root_account.id()))
+            .ok_or_else(|| {
+                anyhow!(
+                    "no key known for '{}' and it is not a subaccount of '{}'",
+                    account_id,
+                    root_account.id()
+                )
+            })?;
+        self.create_account(prefix, initial_balance).await
+    }
+
+    /// Deploy contract code to an account, optionally calling an init method with JSON args.
+    pub async fn deploy_contract(
+        &self,
+        account: &Account,
+        wasm: &[u8],
+        init: Option<(&str, serde_json::Value)>,
+    ) -> anyhow::Result<Contract> {
+        let _span = tracing::info_span!("deploying contract");
+        let contract = account.deploy(wasm).await?.into_result()?;
+        tracing::info!(id = %contract.id(), code_size = wasm.len(), "contract deployed");
+
+        if let Some((method, args)) = init {
+            contract
+                .call(method)
+                .args_json(args)
+                .max_gas()
+                .transact()
+                .await?
+                .into_result()?;
+            tracing::info!(id = %contract.id(), method, "contract initialized");
+        }
+
+        Ok(contract)
+    }
+
     pub async fn gen_rotating_keys(
         &self,
         account: &Account,
@@ -110,6 +199,181 @@ impl NearcoreCtx {
         Ok(keys)
     }
 
+    /// Set a single contract storage key to the given raw value.
+    pub async fn patch_state(
+        &self,
+        account_id: &AccountId,
+        key: &[u8],
+        value: &[u8],
+    ) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("patching contract state");
+        self.worker.patch_state(account_id, key, value).await?;
+        tracing::info!(%account_id, key_len = key.len(), value_len = value.len(), "patched contract state");
+        Ok(())
+    }
+
+    /// Remove contract storage keys.
+    ///
+    /// Sandbox state patching can only write records, so the account is deleted and then
+    /// rebuilt (balance, access keys, code and remaining storage) without the removed keys.
+    /// The deletion pays the account's balance out to the root account, and only that amount
+    /// is taken back from root afterwards. If deleting or rebuilding fails, the account is
+    /// restored as it was before returning the error.
+    pub async fn delete_state(&self, account_id: &AccountId, keys: &[Vec<u8>]) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("deleting contract state");
+        let details = self.worker.view_account(account_id).await?;
+        let access_keys: Vec<_> = self
+            .worker
+            .view_access_keys(account_id)
+            .await?
+            .into_iter()
+            .map(|info| (info.public_key, info.access_key))
+            .collect();
+        let code = if details.code_hash == CryptoHash::default() {
+            None
+        } else {
+            Some(self.worker.view_code(account_id).await?)
+        };
+        let state = self.worker.view_state(account_id).await?;
+        let mut remaining_state = state.clone();
+        for key in keys {
+            remaining_state.remove(key);
+        }
+
+        let root_account_id = self.worker.root_account()?.id().clone();
+        let deletion_sk = SecretKey::from_random(KeyType::ED25519);
+        let payout = match self
+            .delete_account_to(account_id, &deletion_sk, &root_account_id)
+            .await
+        {
+            Ok(payout) => payout,
+            Err(err) => {
+                self.restore_account(account_id, &details, &access_keys, code.as_deref(), &state)
+                    .await
+                    .with_context(|| format!("restoring {} after a failed deletion", account_id))?;
+                // The deletion key is only left behind when the account was not deleted.
+                let _ = Account::from_secret_key(account_id.clone(), deletion_sk.clone(), &self.worker)
+                    .delete_key(&deletion_sk.public_key())
+                    .await;
+                return Err(err);
+            }
+        };
+
+        let rebuilt = self
+            .restore_account(account_id, &details, &access_keys, code.as_deref(), &remaining_state)
+            .await;
+        if let Err(err) = rebuilt {
+            self.restore_account(account_id, &details, &access_keys, code.as_deref(), &state)
+                .await
+                .with_context(|| format!("restoring {} after a failed rebuild", account_id))?;
+            return Err(err);
+        }
+
+        // The rebuilt account already has its balance, so take the payout back from root,
+        // keeping whatever else root received or spent in the meantime.
+        self.worker
+            .patch(&root_account_id)
+            .account_from_current(move |account| {
+                let balance = account.balance.as_yoctonear().saturating_sub(payout);
+                AccountDetailsPatch::from(account).balance(NearToken::from_yoctonear(balance))
+            })
+            .transact()
+            .await?;
+
+        tracing::info!(%account_id, deleted_keys = keys.len(), "deleted contract state");
+        Ok(())
+    }
+
+    /// Delete an account with a temporary key, returning the yoctoNEAR paid out to `beneficiary_id`.
+    async fn delete_account_to(
+        &self,
+        account_id: &AccountId,
+        deletion_sk: &SecretKey,
+        beneficiary_id: &AccountId,
+    ) -> anyhow::Result<u128> {
+        // nearcore refuses to delete accounts with large state, so storage usage is zeroed
+        // before signing the deletion.
+        self.worker
+            .patch(account_id)
+            .account_from_current(|account| AccountDetailsPatch::from(account).storage_usage(0))
+            .access_key(deletion_sk.public_key(), AccessKey::full_access())
+            .transact()
+            .await?;
+        let balance_before = self.worker.view_account(beneficiary_id).await?.balance;
+        Account::from_secret_key(account_id.clone(), deletion_sk.clone(), &self.worker)
+            .delete_account(beneficiary_id)
+            .await?
+            .into_result()?;
+        let balance_after = self.worker.view_account(beneficiary_id).await?.balance;
+        Ok(balance_after
+            .as_yoctonear()
+            .saturating_sub(balance_before.as_yoctonear()))
+    }
+
+    /// Write an account's details, access keys, code and storage back through state patching.
+    async fn restore_account(
+        &self,
+        account_id: &AccountId,
+        details: &AccountDetails,
+        access_keys: &[(PublicKey, AccessKey)],
+        code: Option<&[u8]>,
+        state: &HashMap<Vec<u8>, Vec<u8>>,
+    ) -> anyhow::Result<()> {
+        let mut patch = self
+            .worker
+            .patch(account_id)
+            .account(details.clone().into())
+            .access_keys(access_keys.iter().cloned())
+            .states(state.iter().map(|(key, value)| (key.as_slice(), value.as_slice())));
+        if let Some(code) = code {
+            patch = patch.code(code);
+        }
+        patch.transact().await?;
+        Ok(())
+    }
+
+    /// Replace the contract code of an account without redeploying.
+    pub async fn patch_code(&self, account_id: &AccountId, wasm: &[u8]) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("patching contract code");
+        self.worker.patch(account_id).code(wasm).transact().await?;
+        tracing::info!(%account_id, code_size = wasm.len(), "patched contract code");
+        Ok(())
+    }
+
+    /// Overwrite the balance of an account.
+    pub async fn set_balance(&self, account_id: &AccountId, balance: NearToken) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("patching account balance");
+        self.worker
+            .patch(account_id)
+            .account_from_current(move |account| AccountDetailsPatch::from(account).balance(balance))
+            .transact()
+            .await?;
+        tracing::info!(%account_id, %balance, "patched account balance");
+        Ok(())
+    }
+
+    /// Add (or overwrite) an access key of an account.
+    pub async fn add_access_key(
+        &self,
+        account_id: &AccountId,
+        public_key: PublicKey,
+        access_key: AccessKey,
+    ) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("patching access key");
+        self.worker
+            .patch(account_id)
+            .access_key(public_key.clone(), access_key)
+            .transact()
+            .await?;
+        tracing::info!(%account_id, %public_key, "patched access key");
+        Ok(())
+    }
+
+    /// Snapshot an account's balance, access keys, code hash and storage.
+    pub async fn dump_account(&self, account_id: &AccountId) -> anyhow::Result<AccountDump> {
+        AccountDump::fetch(&self.worker, account_id).await
+    }
+
     /// Get the address the context is using to connect to the RPC of the network.
     pub fn rpc_address(&self) -> String {
         self.worker.rpc_addr()
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::fs;
 use std::rc::Rc;
+use std::time::Duration;
 
 use near_token::NearToken;
 use serde_json::{json, Value};
@@ -11,6 +12,9 @@ use crate::containers::hasura_auth::HasuraAuth;
 use crate::containers::queryapi_postgres::QueryApiPostgres;
 use crate::containers::hasura_graphql::HasuraGraphql;
 use crate::containers::runner::Runner;
+use crate::hasura_client::{GraphQlError, HasuraClient, HasuraRole};
+use crate::indexer_registry::IndexerRegistry;
+use crate::wait::{poll_until, WAIT_TIMEOUT};
 
 use super::nearcore::NearcoreCtx;
 
@@ -20,6 +24,8 @@ pub struct QueryApiCtx<'a> {
     pub hasura_graphql: HasuraGraphql<'a>,
     pub coordinator: Coordinator<'a>,
     pub runner: Runner<'a>,
+    pub registry: IndexerRegistry,
+    redis_host_address: String,
 }
 
 impl<'a> QueryApiCtx<'a> {
@@ -27,6 +33,7 @@ impl<'a> QueryApiCtx<'a> {
         docker_client: &'a DockerClient,
         network: &str,
         redis_address: &str,
+        redis_host_address: &str,
         s3_address: &str,
         s3_bucket_name: &str,
         s3_region: &str,
@@ -70,7 +77,102 @@ impl<'a> QueryApiCtx<'a> {
             postgres,
             hasura_graphql,
             coordinator,
-            runner
+            runner,
+            registry: IndexerRegistry::new(registry_holder, registry_contract.id().clone()),
+            redis_host_address: redis_host_address.to_string(),
         })
     }
+
+    /// Register an indexer under the registry account and wait until the coordinator picks it up.
+    pub async fn register_indexer(
+        &self,
+        name: &str,
+        code: &str,
+        schema: &str,
+        filter: &Value,
+        start_block: Option<u64>,
+    ) -> anyhow::Result<()> {
+        self.registry
+            .register(name, code, schema, filter, start_block)
+            .await?;
+        self.registry
+            .wait_for_coordinator(&self.redis_host_address, name, Duration::from_secs(60))
+            .await
+    }
+
+    pub async fn list_indexers(&self) -> anyhow::Result<Value> {
+        self.registry.list().await
+    }
+
+    pub async fn remove_indexer(&self, name: &str) -> anyhow::Result<()> {
+        self.registry.remove(name).await
+    }
+
+    /// Wait until the runner has processed `height` for an indexer. `indexer` is either the
+    /// indexer name under the registry account or a full `<account_id>/<name>`.
+    pub async fn wait_for_block(&self, indexer: &str, height: u64) -> anyhow::Result<()> {
+        self.wait_for_block_with_timeout(indexer, height, WAIT_TIMEOUT).await
+    }
+
+    /// Same as [`QueryApiCtx::wait_for_block`], failing after `timeout`.
+    pub async fn wait_for_block_with_timeout(
+        &self,
+        indexer: &str,
+        height: u64,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let function_name = if indexer.contains('/') {
+            indexer.to_string()
+        } else {
+            format!("{}/{}", self.registry.account.id(), indexer)
+        };
+        let what = format!("indexer {} to reach block {}", function_name, height);
+        poll_until(&what, timeout, || async {
+            Ok(self
+                .indexer_block_height(&function_name)
+                .await?
+                .map_or(false, |current| current >= height))
+        })
+        .await
+    }
+
+    /// Client for the Hasura instance, reachable from the host.
+    pub fn hasura_client(&self) -> HasuraClient {
+        HasuraClient::new(
+            &self.hasura_graphql.host_address_ipv4(),
+            &self.hasura_graphql.hasura_password(),
+        )
+    }
+
+    /// Last block height processed by an indexer, as recorded by the runner in `indexer_state`.
+    /// `None` until the runner has recorded any state for it.
+    pub async fn indexer_block_height(&self, function_name: &str) -> anyhow::Result<Option<u64>> {
+        let queried = self
+            .hasura_client()
+            .query(
+                &HasuraRole::Admin,
+                "query IndexerState($function_name: String!) { \
+                    indexer_state(where: { function_name: { _eq: $function_name } }) { current_block_height } }",
+                json!({ "function_name": function_name }),
+            )
+            .await;
+        let data = match queried {
+            // The runner only tracks `indexer_state` once it starts the first indexer.
+            Err(err)
+                if err
+                    .downcast_ref::<GraphQlError>()
+                    .map_or(false, |err| err.is_unknown_field("indexer_state")) =>
+            {
+                return Ok(None)
+            }
+            queried => queried?,
+        };
+        let current_block_height = &data["indexer_state"][0]["current_block_height"];
+        // `numeric` columns are returned as numbers or strings depending on their size.
+        Ok(current_block_height.as_u64().or_else(|| {
+            current_block_height
+                .as_str()
+                .and_then(|height| height.parse().ok())
+        }))
+    }
 }
@@ -1,16 +1,50 @@
 use super::nearcore::NearcoreCtx;
 use crate::client::DockerClient;
 use crate::containers::redis::Redis;
-use crate::containers::relayer::Relayer;
+use crate::containers::relayer::{Relayer, RelayerPolicy};
+use crate::relayer_client::RelayerClient;
+use crate::relayer_monitor::{self, RelayerMonitorConfig, RelayerReport};
+use anyhow::anyhow;
+use std::collections::HashSet;
 use near_token::NearToken;
-use near_workspaces::types::SecretKey;
-use near_workspaces::Account;
+use near_workspaces::types::{PublicKey, SecretKey};
+use near_workspaces::{Account, AccountId};
+
+/// Redis has 16 databases and database 0 belongs to the default relayer.
+const MAX_INSTANCES: usize = 15;
+
+/// Longest valid NEAR account id.
+const ACCOUNT_ID_MAX_LEN: usize = 64;
+
+/// Configuration of an additional named relayer, served at `relayer-<name>.nearhat`.
+#[derive(Clone, Debug)]
+pub struct RelayerInstanceConfig {
+    /// Lowercase letters and digits, optionally separated by single dashes.
+    pub name: String,
+    /// Initial balance of the instance's own relayer account.
+    pub initial_balance: NearToken,
+    pub policy: RelayerPolicy,
+}
+
+/// Additional named relayer with its own account, rotating keys and Redis database.
+pub struct RelayerInstance<'a> {
+    pub name: String,
+    pub relayer: Relayer<'a>,
+    pub relayer_account: Account,
+    pub relayer_account_keys: Vec<SecretKey>,
+    pub redis_db: u32,
+}
 
 pub struct RelayerCtx<'a> {
     pub redis: Redis<'a>,
     pub relayer: Relayer<'a>,
+    pub relayer_account: Account,
+    pub relayer_account_keys: Vec<SecretKey>,
     pub creator_account: Account,
     pub creator_account_keys: Vec<SecretKey>,
+    pub instances: Vec<RelayerInstance<'a>>,
+    /// Whether a local social DB is deployed, see [`with_shared_storage`].
+    shared_storage: bool,
 }
 
 impl<'a> RelayerCtx<'a> {
@@ -18,7 +52,13 @@ impl<'a> RelayerCtx<'a> {
         docker_client: &'a DockerClient,
         network: &str,
         nearcore_ctx: &NearcoreCtx,
+        policy: &RelayerPolicy,
+        social_db_contract_id: Option<&AccountId>,
+        instance_configs: &[RelayerInstanceConfig],
     ) -> anyhow::Result<RelayerCtx<'a>> {
+        let root_account_id = nearcore_ctx.worker.root_account()?.id().clone();
+        validate_instance_configs(instance_configs, &root_account_id)?;
+
         let accounts_span = tracing::info_span!("initializing relayer accounts");
         let relayer_account = nearcore_ctx
             .create_account("relayer", NearToken::from_near(1000))
@@ -41,26 +81,380 @@ impl<'a> RelayerCtx<'a> {
         );
         drop(accounts_span);
 
+        // Shared storage is only usable when a real social DB is deployed locally.
+        let social_db_contract_id = match social_db_contract_id {
+            Some(social_db_contract_id) => {
+                Self::initialize_shared_storage(&social_account, social_db_contract_id).await?;
+                social_db_contract_id.clone()
+            }
+            None => "no_social_db.near".parse()?,
+        };
+        let shared_storage = social_db_contract_id.as_str() != "no_social_db.near";
+
         let redis = Redis::run(docker_client, network).await?;
+        let policy = with_shared_storage(policy, shared_storage);
         let relayer = Relayer::run(
             docker_client,
             network,
             &nearcore_ctx.rpc_address(),
-            &redis.host_redis_address_ipv4(),
+            &redis.redis_db_address(0),
             relayer_account.id(),
             &relayer_account_keys,
             creator_account.id(),
-            &"no_social_db.near".parse()?,
+            &social_db_contract_id,
             social_account.id(),
             social_account.secret_key(),
+            &policy,
         )
         .await?;
 
-        Ok(RelayerCtx::<'a> {
+        let mut instances = Vec::with_capacity(instance_configs.len());
+        for (index, instance_config) in instance_configs.iter().enumerate() {
+            let _span = tracing::info_span!("initializing relayer instance");
+            // Database 0 belongs to the default relayer.
+            let redis_db = index as u32 + 1;
+            let relayer_account = nearcore_ctx
+                .create_account(
+                    &format!("relayer-{}", instance_config.name),
+                    instance_config.initial_balance,
+                )
+                .await?;
+            let relayer_account_keys = nearcore_ctx.gen_rotating_keys(&relayer_account, 5).await?;
+            let relayer = Relayer::run(
+                docker_client,
+                network,
+                &nearcore_ctx.rpc_address(),
+                &redis.redis_db_address(redis_db),
+                relayer_account.id(),
+                &relayer_account_keys,
+                creator_account.id(),
+                &social_db_contract_id,
+                social_account.id(),
+                social_account.secret_key(),
+                &with_shared_storage(&instance_config.policy, shared_storage),
+            )
+            .await?;
+            tracing::info!(
+                name = instance_config.name,
+                relayer_account = %relayer_account.id(),
+                redis_db,
+                "relayer instance initialized"
+            );
+            instances.push(RelayerInstance {
+                name: instance_config.name.clone(),
+                relayer,
+                relayer_account,
+                relayer_account_keys,
+                redis_db,
+            });
+        }
+
+        let relayer_ctx = RelayerCtx::<'a> {
             redis,
             relayer,
+            relayer_account,
+            relayer_account_keys,
             creator_account,
             creator_account_keys,
-        })
+            instances,
+            shared_storage,
+        };
+        relayer_ctx.seed_allowances(docker_client, 0, &policy).await?;
+        for (instance, instance_config) in relayer_ctx.instances.iter().zip(instance_configs) {
+            relayer_ctx
+                .seed_allowances(docker_client, instance.redis_db, &instance_config.policy)
+                .await?;
+        }
+        Ok(relayer_ctx)
+    }
+
+    /// Fund the social DB shared storage pool owned by the relayer's shared storage account.
+    async fn initialize_shared_storage(
+        social_account: &Account,
+        social_db_contract_id: &AccountId,
+    ) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("initializing relayer shared storage");
+        social_account
+            .call(social_db_contract_id, "shared_storage_pool_deposit")
+            .args_json(serde_json::json!({ "owner_id": social_account.id() }))
+            .deposit(NearToken::from_near(100))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        tracing::info!(
+            social_account = %social_account.id(),
+            %social_db_contract_id,
+            "relayer shared storage initialized"
+        );
+        Ok(())
+    }
+
+    /// Look up a named relayer instance.
+    pub fn instance(&self, name: &str) -> Option<&RelayerInstance<'a>> {
+        self.instances.iter().find(|instance| instance.name == name)
+    }
+
+    /// Client for the relayer HTTP API, reachable from the host.
+    pub fn client(&self, nearcore_ctx: &NearcoreCtx) -> RelayerClient {
+        RelayerClient::new(&self.relayer.host_http_address_ipv4(), &nearcore_ctx.worker)
+    }
+
+    /// Client for a named relayer instance.
+    pub fn instance_client(&self, name: &str, nearcore_ctx: &NearcoreCtx) -> anyhow::Result<RelayerClient> {
+        let instance = self
+            .instance(name)
+            .ok_or_else(|| anyhow!("no relayer instance named '{}'", name))?;
+        Ok(RelayerClient::new(
+            &instance.relayer.host_http_address_ipv4(),
+            &nearcore_ctx.worker,
+        ))
+    }
+
+    /// Balance, per-key nonces and pending transactions of the default relayer account.
+    pub async fn report(&self, nearcore_ctx: &NearcoreCtx) -> anyhow::Result<RelayerReport> {
+        RelayerReport::fetch(
+            &nearcore_ctx.worker,
+            self.relayer_account.id(),
+            &public_keys(&self.relayer_account_keys),
+        )
+        .await
+    }
+
+    /// Same as [`RelayerCtx::report`], for a named relayer instance.
+    pub async fn instance_report(
+        &self,
+        name: &str,
+        nearcore_ctx: &NearcoreCtx,
+    ) -> anyhow::Result<RelayerReport> {
+        let instance = self
+            .instance(name)
+            .ok_or_else(|| anyhow!("no relayer instance named '{}'", name))?;
+        RelayerReport::fetch(
+            &nearcore_ctx.worker,
+            instance.relayer_account.id(),
+            &public_keys(&instance.relayer_account_keys),
+        )
+        .await
+    }
+
+    /// Top up the default relayer and all instances from the root account if their balance is
+    /// below `threshold`.
+    pub async fn top_up(
+        &self,
+        nearcore_ctx: &NearcoreCtx,
+        threshold: NearToken,
+        amount: NearToken,
+    ) -> anyhow::Result<()> {
+        let accounts = std::iter::once(&self.relayer_account)
+            .chain(self.instances.iter().map(|instance| &instance.relayer_account));
+        for account in accounts {
+            relayer_monitor::top_up(&nearcore_ctx.worker, account.id(), threshold, amount).await?;
+        }
+        Ok(())
+    }
+
+    /// Periodically log reports for the default relayer and all instances, topping them up
+    /// according to `config`. Abort the returned handle to stop monitoring.
+    pub fn spawn_monitor(
+        &self,
+        nearcore_ctx: &NearcoreCtx,
+        config: RelayerMonitorConfig,
+    ) -> tokio::task::JoinHandle<()> {
+        let accounts = std::iter::once((self.relayer_account.id(), &self.relayer_account_keys))
+            .chain(self.instances.iter().map(|instance| {
+                (instance.relayer_account.id(), &instance.relayer_account_keys)
+            }))
+            .map(|(account_id, keys)| (account_id.clone(), public_keys(keys)))
+            .collect();
+        relayer_monitor::spawn_monitor(nearcore_ctx.worker.clone(), accounts, config)
+    }
+
+    /// Replace whitelists, feature flags and allowances at runtime. The relayer container is
+    /// replaced with one running a regenerated config, on the same host port.
+    pub async fn update_policy(
+        &mut self,
+        docker_client: &'a DockerClient,
+        policy: &RelayerPolicy,
+    ) -> anyhow::Result<()> {
+        let policy = with_shared_storage(policy, self.shared_storage);
+        self.relayer
+            .update_policy(docker_client, &policy, self.creator_account.id())
+            .await?;
+        self.seed_allowances(docker_client, 0, &policy).await
+    }
+
+    /// Same as [`RelayerCtx::update_policy`], for a named relayer instance.
+    pub async fn update_instance_policy(
+        &mut self,
+        docker_client: &'a DockerClient,
+        name: &str,
+        policy: &RelayerPolicy,
+    ) -> anyhow::Result<()> {
+        let policy = with_shared_storage(policy, self.shared_storage);
+        let instance = self
+            .instances
+            .iter_mut()
+            .find(|instance| instance.name == name)
+            .ok_or_else(|| anyhow!("no relayer instance named '{}'", name))?;
+        instance
+            .relayer
+            .update_policy(docker_client, &policy, self.creator_account.id())
+            .await?;
+        let redis_db = instance.redis_db;
+        self.seed_allowances(docker_client, redis_db, &policy).await
+    }
+
+    /// Replace only the contract and delegate action receiver whitelists, keeping other settings.
+    pub async fn update_whitelist(
+        &mut self,
+        docker_client: &'a DockerClient,
+        whitelisted_contracts: Vec<AccountId>,
+        whitelisted_delegate_action_receiver_ids: Vec<AccountId>,
+    ) -> anyhow::Result<()> {
+        let policy = with_shared_storage(
+            &RelayerPolicy {
+                whitelisted_contracts,
+                whitelisted_delegate_action_receiver_ids,
+                ..self.relayer.policy.clone()
+            },
+            self.shared_storage,
+        );
+        self.relayer
+            .update_policy(docker_client, &policy, self.creator_account.id())
+            .await
+    }
+
+    // The relayer keeps allowances in Redis as `<account_id> -> <allowance in gas>`.
+    async fn seed_allowances(
+        &self,
+        docker_client: &DockerClient,
+        redis_db: u32,
+        policy: &RelayerPolicy,
+    ) -> anyhow::Result<()> {
+        for (account_id, allowance_in_gas) in &policy.allowances {
+            self.redis
+                .set(docker_client, redis_db, account_id.as_str(), &allowance_in_gas.to_string())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Instance names end up in the `relayer-<name>.nearhat` hostname and the `relayer-<name>`
+/// account id, and every instance takes a Redis database.
+/// Each instance gets the account `relayer-<name>.<root>`, which bounds the name length.
+fn validate_instance_configs(
+    instance_configs: &[RelayerInstanceConfig],
+    root_account_id: &AccountId,
+) -> anyhow::Result<()> {
+    if instance_configs.len() > MAX_INSTANCES {
+        return Err(anyhow!(
+            "{} relayer instances requested, at most {} are supported",
+            instance_configs.len(),
+            MAX_INSTANCES
+        ));
+    }
+    let max_name_len = ACCOUNT_ID_MAX_LEN.saturating_sub("relayer-.".len() + root_account_id.as_str().len());
+    let mut names = HashSet::new();
+    for instance_config in instance_configs {
+        let name = instance_config.name.as_str();
+        if name.len() > max_name_len {
+            return Err(anyhow!(
+                "relayer instance name '{}' is too long: account 'relayer-{}.{}' would exceed {} characters, use at most {}",
+                name,
+                name,
+                root_account_id,
+                ACCOUNT_ID_MAX_LEN,
+                max_name_len
+            ));
+        }
+        let valid = name.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .bytes()
+                    .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+        });
+        if !valid {
+            return Err(anyhow!(
+                "invalid relayer instance name '{}': use lowercase letters and digits, optionally separated by single dashes",
+                name
+            ));
+        }
+        if !names.insert(name) {
+            return Err(anyhow!("duplicate relayer instance name '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// Shared storage defaults to on when a local social DB is deployed, unless the policy sets it.
+fn with_shared_storage(policy: &RelayerPolicy, shared_storage: bool) -> RelayerPolicy {
+    let mut policy = policy.clone();
+    if shared_storage {
+        policy.use_shared_storage.get_or_insert(true);
+    }
+    policy
+}
+
+fn public_keys(keys: &[SecretKey]) -> Vec<PublicKey> {
+    keys.iter().map(|key| key.public_key()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn root() -> AccountId {
+        "test.near".parse().unwrap()
+    }
+
+    fn instances(names: &[&str]) -> Vec<RelayerInstanceConfig> {
+        names
+            .iter()
+            .map(|name| RelayerInstanceConfig {
+                name: name.to_string(),
+                initial_balance: NearToken::from_near(1),
+                policy: RelayerPolicy::default(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_hostname_safe_names() {
+        validate_instance_configs(&instances(&["sponsor", "pay-with-ft", "r2"]), &root()).unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        for name in ["", "Sponsor", "pay_with_ft", "a.b", "-a", "a-", "a--b"] {
+            assert!(validate_instance_configs(&instances(&[name]), &root()).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn caps_names_at_the_account_id_length() {
+        // `relayer-<name>.test.near` is a valid account id for names of up to 46 characters.
+        let longest = "a".repeat(46);
+        validate_instance_configs(&instances(&[longest.as_str()]), &root()).unwrap();
+        format!("relayer-{longest}.test.near").parse::<AccountId>().unwrap();
+
+        let too_long = "a".repeat(47);
+        let err = validate_instance_configs(&instances(&[too_long.as_str()]), &root()).unwrap_err();
+        assert!(err.to_string().contains("too long"), "{err}");
+    }
+
+    #[test]
+    fn rejects_duplicate_names() {
+        let err = validate_instance_configs(&instances(&["sponsor", "sponsor"]), &root()).unwrap_err();
+        assert!(err.to_string().contains("duplicate"), "{err}");
+    }
+
+    #[test]
+    fn rejects_more_instances_than_redis_databases() {
+        let names: Vec<String> = (0..=MAX_INSTANCES).map(|i| format!("r{i}")).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        validate_instance_configs(&instances(&names[..MAX_INSTANCES]), &root()).unwrap();
+        assert!(validate_instance_configs(&instances(&names), &root()).is_err());
     }
 }
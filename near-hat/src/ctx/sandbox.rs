@@ -0,0 +1,40 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::client::DockerClient;
+use crate::containers::sandbox::Sandbox;
+use crate::validator::ValidatorContainer;
+use near_workspaces::network::{Sandbox as SandboxNetwork, ValidatorKey};
+use near_workspaces::Worker;
+use serde_json::{json, Value};
+
+/// Lightweight context running only a nearcore sandbox node (no Lake, no LocalStack).
+pub struct SandboxCtx<'a> {
+    pub sandbox: Sandbox<'a>,
+    pub worker: Worker<SandboxNetwork>,
+}
+
+impl<'a> SandboxCtx<'a> {
+    pub async fn new(
+        docker_client: &'a DockerClient,
+        network: &str,
+        key_json_ref: Rc<RefCell<Value>>,
+    ) -> anyhow::Result<SandboxCtx<'a>> {
+        let sandbox = Sandbox::run(docker_client, network).await?;
+
+        let validator_key = sandbox.fetch_keys(docker_client).await?;
+
+        tracing::info!("initializing sandbox worker");
+        let worker = near_workspaces::sandbox()
+            .rpc_addr(&sandbox.host_rpc_address_ipv4())
+            .validator_key(ValidatorKey::Known(
+                validator_key.account_id.to_string().parse()?,
+                validator_key.secret_key.to_string().parse()?,
+            ))
+            .await?;
+
+        key_json_ref.borrow_mut()[validator_key.account_id.to_string()] = json!(validator_key.secret_key.to_string());
+
+        Ok(SandboxCtx { sandbox, worker })
+    }
+}
@@ -0,0 +1,257 @@
+use anyhow::anyhow;
+use near_workspaces::result::ExecutionFinalResult;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::lake_bucket::LakeBucket;
+use crate::tx_tracer::TxTrace;
+
+/// Prefix of NEP-297 event logs.
+pub const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+
+/// A NEP-297 event, e.g. an NEP-141 `ft_transfer` or an NEP-171 `nft_mint`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Nep297Event {
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    #[serde(default)]
+    pub data: Value,
+}
+
+impl Nep297Event {
+    /// Parse an `EVENT_JSON:` log, `None` for other logs and malformed events.
+    pub fn from_log(log: &str) -> Option<Nep297Event> {
+        let json = log.strip_prefix(EVENT_LOG_PREFIX)?;
+        serde_json::from_str(json.trim()).ok()
+    }
+}
+
+/// An event together with the contract that emitted it.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EmittedEvent {
+    pub contract_id: String,
+    /// Height of the block the emitting receipt was executed in, when read from Lake.
+    pub block_height: Option<u64>,
+    #[serde(flatten)]
+    pub event: Nep297Event,
+}
+
+/// Selects events by emitting contract, standard and event name. Unset fields match anything.
+#[derive(Clone, Debug, Default)]
+pub struct EventFilter {
+    pub contract_id: Option<String>,
+    pub standard: Option<String>,
+    pub event: Option<String>,
+}
+
+impl EventFilter {
+    pub fn new() -> EventFilter {
+        EventFilter::default()
+    }
+
+    pub fn contract_id(mut self, contract_id: impl Into<String>) -> EventFilter {
+        self.contract_id = Some(contract_id.into());
+        self
+    }
+
+    pub fn standard(mut self, standard: impl Into<String>) -> EventFilter {
+        self.standard = Some(standard.into());
+        self
+    }
+
+    pub fn event(mut self, event: impl Into<String>) -> EventFilter {
+        self.event = Some(event.into());
+        self
+    }
+
+    pub fn matches(&self, emitted: &EmittedEvent) -> bool {
+        self.contract_id.as_ref().map_or(true, |id| *id == emitted.contract_id)
+            && self.standard.as_ref().map_or(true, |standard| *standard == emitted.event.standard)
+            && self.event.as_ref().map_or(true, |event| *event == emitted.event.event)
+    }
+
+    /// Events matching the filter, in the order they were emitted.
+    pub fn apply(&self, events: Vec<EmittedEvent>) -> Vec<EmittedEvent> {
+        events.into_iter().filter(|event| self.matches(event)).collect()
+    }
+}
+
+fn emitted_events<'a>(
+    contract_id: &str,
+    block_height: Option<u64>,
+    logs: impl IntoIterator<Item = &'a str>,
+) -> Vec<EmittedEvent> {
+    logs.into_iter()
+        .filter_map(Nep297Event::from_log)
+        .map(|event| EmittedEvent {
+            contract_id: contract_id.to_string(),
+            block_height,
+            event,
+        })
+        .collect()
+}
+
+/// Events emitted while executing a transaction sent through `near-workspaces`.
+pub fn events_from_result(result: &ExecutionFinalResult) -> Vec<EmittedEvent> {
+    result
+        .outcomes()
+        .into_iter()
+        .flat_map(|outcome| {
+            emitted_events(
+                outcome.executor_id.as_str(),
+                None,
+                outcome.logs.iter().map(String::as_str),
+            )
+        })
+        .collect()
+}
+
+/// Events emitted by a traced transaction and all of its receipts.
+pub fn events_from_trace(trace: &TxTrace) -> Vec<EmittedEvent> {
+    trace
+        .all_receipts()
+        .into_iter()
+        .flat_map(|receipt| {
+            emitted_events(
+                &receipt.receiver_id,
+                Some(receipt.block_height),
+                receipt.outcome.logs.iter().map(String::as_str),
+            )
+        })
+        .collect()
+}
+
+/// Events emitted in blocks `[from_height, to_height]` of a Lake bucket.
+pub async fn events_in_blocks(
+    bucket: &LakeBucket,
+    from_height: u64,
+    to_height: u64,
+) -> anyhow::Result<Vec<EmittedEvent>> {
+    let mut events = Vec::new();
+    for height in bucket.heights(Some(from_height), Some(to_height)).await? {
+        events.extend(events_in_block(bucket, height).await?);
+    }
+    Ok(events)
+}
+
+/// Events emitted in the block at `height` of a Lake bucket.
+pub(crate) async fn events_in_block(bucket: &LakeBucket, height: u64) -> anyhow::Result<Vec<EmittedEvent>> {
+    let mut events = Vec::new();
+    for shard in bucket.shards(height).await? {
+        for receipt_outcome in shard["receipt_execution_outcomes"].as_array().into_iter().flatten() {
+            let outcome = &receipt_outcome["execution_outcome"]["outcome"];
+            events.extend(emitted_events(
+                outcome["executor_id"].as_str().unwrap_or_default(),
+                Some(height),
+                outcome["logs"].as_array().into_iter().flatten().filter_map(Value::as_str),
+            ));
+        }
+    }
+    Ok(events)
+}
+
+/// Return the first event matching `filter`, or an error listing all events that were emitted.
+pub fn assert_event_emitted(events: &[EmittedEvent], filter: &EventFilter) -> anyhow::Result<EmittedEvent> {
+    events
+        .iter()
+        .find(|event| filter.matches(event))
+        .cloned()
+        .ok_or_else(|| {
+            anyhow!(
+                "no event matching {:?} was emitted, got: {}",
+                filter,
+                serde_json::to_string(events).unwrap_or_default()
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn emitted(contract_id: &str, standard: &str, event: &str) -> EmittedEvent {
+        EmittedEvent {
+            contract_id: contract_id.to_string(),
+            block_height: None,
+            event: Nep297Event {
+                standard: standard.to_string(),
+                version: "1.0.0".to_string(),
+                event: event.to_string(),
+                data: Value::Null,
+            },
+        }
+    }
+
+    #[test]
+    fn parses_event_logs() {
+        let log = r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_transfer","data":[{"old_owner_id":"alice.near","new_owner_id":"bob.near","amount":"1"}]}"#;
+        let event = Nep297Event::from_log(log).unwrap();
+        assert_eq!(event.standard, "nep141");
+        assert_eq!(event.version, "1.0.0");
+        assert_eq!(event.event, "ft_transfer");
+        assert_eq!(event.data[0]["amount"], json!("1"));
+    }
+
+    #[test]
+    fn parses_event_logs_without_data_and_with_whitespace() {
+        let log = r#"EVENT_JSON: {"standard":"nep171","version":"1.0.0","event":"nft_mint"} "#;
+        let event = Nep297Event::from_log(log).unwrap();
+        assert_eq!(event.event, "nft_mint");
+        assert_eq!(event.data, Value::Null);
+    }
+
+    #[test]
+    fn ignores_other_and_malformed_logs() {
+        for log in [
+            "Transfer 1 from alice.near to bob.near",
+            r#"{"standard":"nep141","version":"1.0.0","event":"ft_transfer"}"#,
+            "EVENT_JSON:",
+            "EVENT_JSON:{not json}",
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0"}"#,
+            r#"event_json:{"standard":"nep141","version":"1.0.0","event":"ft_transfer"}"#,
+        ] {
+            assert_eq!(Nep297Event::from_log(log), None, "{log}");
+        }
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        assert!(EventFilter::new().matches(&emitted("ft.near", "nep141", "ft_transfer")));
+    }
+
+    #[test]
+    fn filter_matches_all_set_fields() {
+        let filter = EventFilter::new()
+            .contract_id("ft.near")
+            .standard("nep141")
+            .event("ft_transfer");
+        assert!(filter.matches(&emitted("ft.near", "nep141", "ft_transfer")));
+        assert!(!filter.matches(&emitted("other.near", "nep141", "ft_transfer")));
+        assert!(!filter.matches(&emitted("ft.near", "nep171", "ft_transfer")));
+        assert!(!filter.matches(&emitted("ft.near", "nep141", "ft_mint")));
+    }
+
+    #[test]
+    fn filter_apply_keeps_order() {
+        let events = vec![
+            emitted("ft.near", "nep141", "ft_mint"),
+            emitted("nft.near", "nep171", "nft_mint"),
+            emitted("ft.near", "nep141", "ft_transfer"),
+        ];
+        let matching = EventFilter::new().contract_id("ft.near").apply(events);
+        let names = matching.iter().map(|e| e.event.event.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["ft_mint", "ft_transfer"]);
+    }
+
+    #[test]
+    fn assert_event_emitted_lists_events_on_failure() {
+        let events = vec![emitted("ft.near", "nep141", "ft_mint")];
+        let found = assert_event_emitted(&events, &EventFilter::new().event("ft_mint")).unwrap();
+        assert_eq!(found, events[0]);
+
+        let err = assert_event_emitted(&events, &EventFilter::new().event("ft_burn")).unwrap_err();
+        assert!(err.to_string().contains("ft_mint"), "{err}");
+    }
+}
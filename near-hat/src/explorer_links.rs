@@ -0,0 +1,52 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use near_workspaces::result::{ExecutionFinalResult, ExecutionSuccess};
+
+/// Explorer frontend behind the NEARHat reverse proxy.
+pub const EXPLORER_PROXY_URL: &str = "http://explorer.nearhat";
+
+/// Builds explorer URLs for transactions, accounts and blocks.
+#[derive(Clone, Debug)]
+pub struct ExplorerLinks {
+    base_url: String,
+}
+
+impl ExplorerLinks {
+    pub fn new(base_url: &str) -> ExplorerLinks {
+        ExplorerLinks {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Links through the reverse proxy, see [`EXPLORER_PROXY_URL`].
+    pub fn proxy() -> ExplorerLinks {
+        ExplorerLinks::new(EXPLORER_PROXY_URL)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn tx_url(&self, transaction_hash: impl Display) -> String {
+        format!("{}/transactions/{}", self.base_url, transaction_hash)
+    }
+
+    pub fn account_url(&self, account_id: impl Display) -> String {
+        format!("{}/accounts/{}", self.base_url, account_id)
+    }
+
+    pub fn block_url(&self, height: u64) -> String {
+        format!("{}/blocks/{}", self.base_url, height)
+    }
+
+    /// Like [`ExecutionFinalResult::into_result`], but a failure is logged and returned together
+    /// with a link to the transaction, so a failing test points straight at it.
+    pub fn expect_success(&self, result: ExecutionFinalResult) -> anyhow::Result<ExecutionSuccess> {
+        let url = self.tx_url(result.outcome().transaction_hash);
+        result.into_result().map_err(|failure| {
+            tracing::error!(url, "transaction failed");
+            anyhow!("transaction failed ({}): {:?}", url, failure)
+        })
+    }
+}
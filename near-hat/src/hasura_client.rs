@@ -0,0 +1,295 @@
+use std::fmt;
+
+use anyhow::{anyhow, Context};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Role to run a GraphQL query as.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HasuraRole {
+    Admin,
+    /// Role of an indexer owner, see [`HasuraRole::for_account`].
+    Role(String),
+}
+
+impl HasuraRole {
+    /// QueryAPI gives every account a role named after its sanitized account id.
+    pub fn for_account(account_id: &str) -> HasuraRole {
+        HasuraRole::Role(sanitize(account_id))
+    }
+}
+
+/// Postgres schema holding the tables of an indexer.
+pub fn indexer_schema(account_id: &str, indexer_name: &str) -> String {
+    format!("{}_{}", sanitize(account_id), sanitize(indexer_name))
+}
+
+fn sanitize(name: &str) -> String {
+    name.replace(['.', '-'], "_")
+}
+
+/// Log line written by the runner while executing an indexer.
+#[derive(Deserialize, Debug, Clone)]
+pub struct IndexerLogEntry {
+    pub id: Value,
+    pub block_height: Value,
+    pub timestamp: String,
+    pub message: Option<String>,
+}
+
+/// Position in an indexer's logs, advanced by [`HasuraClient::indexer_logs`]. Several entries
+/// can share a timestamp, so the ids already returned at the last timestamp are kept as well.
+#[derive(Clone, Debug, Default)]
+pub struct IndexerLogCursor {
+    timestamp: Option<String>,
+    seen_ids: Vec<Value>,
+}
+
+impl IndexerLogCursor {
+    /// Drop entries returned before and move the cursor past the remaining ones.
+    fn advance(&mut self, entries: Vec<IndexerLogEntry>) -> Vec<IndexerLogEntry> {
+        let new_entries: Vec<IndexerLogEntry> = entries
+            .into_iter()
+            .filter(|entry| {
+                self.timestamp.as_deref() != Some(entry.timestamp.as_str())
+                    || !self.seen_ids.contains(&entry.id)
+            })
+            .collect();
+        for entry in &new_entries {
+            if self.timestamp.as_deref() != Some(entry.timestamp.as_str()) {
+                self.timestamp = Some(entry.timestamp.clone());
+                self.seen_ids.clear();
+            }
+            self.seen_ids.push(entry.id.clone());
+        }
+        new_entries
+    }
+}
+
+/// Errors Hasura returned for a GraphQL query.
+#[derive(Debug)]
+pub struct GraphQlError {
+    pub errors: Value,
+}
+
+impl GraphQlError {
+    /// Whether the query selected a field Hasura does not know, e.g. a table that is not tracked yet.
+    pub fn is_unknown_field(&self, field: &str) -> bool {
+        let not_found = format!("'{}' not found", field);
+        self.errors.as_array().into_iter().flatten().any(|error| {
+            error["extensions"]["code"] == "validation-failed"
+                && error["message"].as_str().map_or(false, |message| message.contains(&not_found))
+        })
+    }
+}
+
+impl fmt::Display for GraphQlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "GraphQL query failed: {}", self.errors)
+    }
+}
+
+impl std::error::Error for GraphQlError {}
+
+/// Typed client for the local Hasura GraphQL and metadata APIs.
+pub struct HasuraClient {
+    http: reqwest::Client,
+    hasura_address: String,
+    admin_secret: String,
+}
+
+impl HasuraClient {
+    pub fn new(hasura_address: &str, admin_secret: &str) -> HasuraClient {
+        HasuraClient {
+            http: reqwest::Client::new(),
+            hasura_address: hasura_address.trim_end_matches('/').to_string(),
+            admin_secret: admin_secret.to_string(),
+        }
+    }
+
+    /// Run a GraphQL query and return its `data`. GraphQL errors are returned as [`GraphQlError`].
+    pub async fn query(&self, role: &HasuraRole, query: &str, variables: Value) -> anyhow::Result<Value> {
+        let mut request = self
+            .http
+            .post(format!("{}/v1/graphql", self.hasura_address))
+            .header("x-hasura-admin-secret", &self.admin_secret);
+        if let HasuraRole::Role(role) = role {
+            request = request.header("x-hasura-role", role);
+        }
+        let mut response: Value = request
+            .json(&json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .context("sending GraphQL query")?
+            .json()
+            .await?;
+        if let Some(errors) = response.get_mut("errors") {
+            return Err(GraphQlError { errors: errors.take() }.into());
+        }
+        Ok(response["data"].take())
+    }
+
+    /// Same as [`HasuraClient::query`], deserializing `data` into `T`.
+    pub async fn query_as<T: DeserializeOwned>(
+        &self,
+        role: &HasuraRole,
+        query: &str,
+        variables: Value,
+    ) -> anyhow::Result<T> {
+        let data = self.query(role, query, variables).await?;
+        serde_json::from_value(data).context("deserializing GraphQL response")
+    }
+
+    /// Run a metadata API operation, e.g. `("export_metadata", json!({}))`.
+    pub async fn metadata(&self, operation: &str, args: Value) -> anyhow::Result<Value> {
+        self.post("/v1/metadata", json!({ "type": operation, "args": args }))
+            .await
+    }
+
+    /// Run raw SQL against the default source.
+    pub async fn run_sql(&self, sql: &str) -> anyhow::Result<Value> {
+        self.post(
+            "/v2/query",
+            json!({ "type": "run_sql", "args": { "source": "default", "sql": sql } }),
+        )
+        .await
+    }
+
+    /// Names of the tables Hasura tracks in a Postgres schema, e.g. an [`indexer_schema`].
+    pub async fn tracked_tables(&self, schema: &str) -> anyhow::Result<Vec<String>> {
+        let metadata = self.metadata("export_metadata", json!({})).await?;
+        let tables = metadata["sources"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|source| source["tables"].as_array().into_iter().flatten())
+            .filter(|table| table["table"]["schema"] == schema)
+            .filter_map(|table| table["table"]["name"].as_str().map(str::to_string))
+            .collect();
+        Ok(tables)
+    }
+
+    /// Drop an indexer's schema together with its Hasura metadata, state and logs, so that the
+    /// runner provisions it from scratch on its next run.
+    pub async fn reset_indexer(&self, account_id: &str, indexer_name: &str) -> anyhow::Result<()> {
+        let schema = indexer_schema(account_id, indexer_name);
+        let function_name = format!("{}/{}", account_id, indexer_name);
+        // Account ids and indexer names are inlined into the SQL below.
+        anyhow::ensure!(
+            !function_name.contains(['\'', '"']),
+            "invalid indexer name '{}'",
+            function_name
+        );
+        let sql = format!(
+            "DROP SCHEMA IF EXISTS \"{schema}\" CASCADE; \
+             DELETE FROM indexer_state WHERE function_name = '{function_name}'; \
+             DELETE FROM indexer_log_entries WHERE function_name = '{function_name}';"
+        );
+        self.post(
+            "/v2/query",
+            json!({ "type": "run_sql", "args": { "source": "default", "sql": sql, "cascade": true } }),
+        )
+        .await?;
+        tracing::info!(schema, function_name, "indexer reset");
+        Ok(())
+    }
+
+    /// Runner logs of an indexer (`<account_id>/<name>`) written since `cursor`, oldest first.
+    /// Start from [`IndexerLogCursor::default`] to get all logs.
+    pub async fn indexer_logs(
+        &self,
+        function_name: &str,
+        cursor: &mut IndexerLogCursor,
+    ) -> anyhow::Result<Vec<IndexerLogEntry>> {
+        let data = self
+            .query(
+                &HasuraRole::Admin,
+                "query IndexerLogs($function_name: String!, $since: timestamp!) { \
+                    indexer_log_entries( \
+                        where: { function_name: { _eq: $function_name }, timestamp: { _gte: $since } }, \
+                        order_by: [{ timestamp: asc }, { id: asc }] \
+                    ) { id block_height timestamp message } }",
+                json!({
+                    "function_name": function_name,
+                    "since": cursor.timestamp.as_deref().unwrap_or("1970-01-01T00:00:00"),
+                }),
+            )
+            .await?;
+        let entries = serde_json::from_value(data["indexer_log_entries"].clone()).context("parsing indexer logs")?;
+        Ok(cursor.advance(entries))
+    }
+
+    async fn post(&self, path: &str, body: Value) -> anyhow::Result<Value> {
+        let url = format!("{}{}", self.hasura_address, path);
+        let response = self
+            .http
+            .post(&url)
+            .header("x-hasura-admin-secret", &self.admin_secret)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("sending request to {url}"))?;
+        let status = response.status();
+        let body: Value = response.json().await?;
+        if !status.is_success() {
+            return Err(anyhow!("Hasura request to {} failed with {}: {}", url, status, body));
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, timestamp: &str) -> IndexerLogEntry {
+        IndexerLogEntry {
+            id: json!(id),
+            block_height: json!(1),
+            timestamp: timestamp.to_string(),
+            message: None,
+        }
+    }
+
+    fn ids(entries: &[IndexerLogEntry]) -> Vec<Value> {
+        entries.iter().map(|entry| entry.id.clone()).collect()
+    }
+
+    #[test]
+    fn log_cursor_keeps_entries_sharing_the_last_timestamp() {
+        let mut cursor = IndexerLogCursor::default();
+        let first = cursor.advance(vec![entry(1, "t1"), entry(2, "t2")]);
+        assert_eq!(ids(&first), vec![json!(1), json!(2)]);
+
+        // A later entry with the same timestamp as the last one seen is still returned.
+        let second = cursor.advance(vec![entry(2, "t2"), entry(3, "t2"), entry(4, "t3")]);
+        assert_eq!(ids(&second), vec![json!(3), json!(4)]);
+
+        let third = cursor.advance(vec![entry(4, "t3")]);
+        assert!(third.is_empty());
+    }
+
+    #[test]
+    fn detects_untracked_tables() {
+        let err = GraphQlError {
+            errors: json!([{
+                "extensions": { "path": "$.selectionSet.indexer_state", "code": "validation-failed" },
+                "message": "field 'indexer_state' not found in type: 'query_root'"
+            }]),
+        };
+        assert!(err.is_unknown_field("indexer_state"));
+        assert!(!err.is_unknown_field("indexer_log_entries"));
+    }
+
+    #[test]
+    fn other_errors_are_not_unknown_fields() {
+        let err = GraphQlError {
+            errors: json!([{
+                "extensions": { "path": "$", "code": "unexpected" },
+                "message": "database query error: 'indexer_state' not found"
+            }]),
+        };
+        assert!(!err.is_unknown_field("indexer_state"));
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use near_workspaces::{Account, AccountId};
+use serde_json::{json, Value};
+
+use crate::containers::redis;
+use crate::wait::poll_until;
+
+/// Redis set where the coordinator records a stream for every indexer it picked up.
+const STREAMS_SET_KEY: &str = "streams";
+
+/// Client for the QueryAPI registry contract. Indexers are registered under the signing account.
+pub struct IndexerRegistry {
+    pub account: Account,
+    pub registry_id: AccountId,
+}
+
+impl IndexerRegistry {
+    pub fn new(account: Account, registry_id: AccountId) -> IndexerRegistry {
+        IndexerRegistry {
+            account,
+            registry_id,
+        }
+    }
+
+    /// Register (or update) an indexer function. Without a start block the indexer follows the
+    /// chain head.
+    pub async fn register(
+        &self,
+        name: &str,
+        code: &str,
+        schema: &str,
+        filter: &Value,
+        start_block: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let _span = tracing::info_span!("registering indexer");
+        self.account
+            .call(&self.registry_id, "register_indexer_function")
+            .args_json(json!({
+                "function_name": name,
+                "code": code,
+                "schema": schema,
+                "filter_json": filter.to_string(),
+                "start_block_height": start_block,
+            }))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        tracing::info!(account_id = %self.account.id(), name, ?start_block, "indexer registered");
+        Ok(())
+    }
+
+    /// All registered indexers, keyed by account id and then by function name.
+    pub async fn list(&self) -> anyhow::Result<Value> {
+        Ok(self
+            .account
+            .view(&self.registry_id, "list_indexer_functions")
+            .args_json(json!({}))
+            .await?
+            .json()?)
+    }
+
+    pub async fn remove(&self, name: &str) -> anyhow::Result<()> {
+        self.account
+            .call(&self.registry_id, "remove_indexer_function")
+            .args_json(json!({ "function_name": name }))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+        tracing::info!(account_id = %self.account.id(), name, "indexer removed");
+        Ok(())
+    }
+
+    /// Poll the coordinator's Redis until it has created a stream for the indexer.
+    pub async fn wait_for_coordinator(
+        &self,
+        redis_address: &str,
+        name: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let prefix = format!("{}/{}:", self.account.id(), name);
+        let what = format!("coordinator to pick up indexer {}/{}", self.account.id(), name);
+        poll_until(&what, timeout, || async {
+            let streams = redis::set_members(redis_address, STREAMS_SET_KEY).await?;
+            Ok(streams.iter().any(|stream| stream.starts_with(&prefix)))
+        })
+        .await?;
+        tracing::info!(name, "indexer picked up by coordinator");
+        Ok(())
+    }
+}
+
+/// Filter matching successful actions on `affected_account_id`, as used by the JS tests.
+pub fn action_filter(affected_account_id: &str) -> Value {
+    json!({
+        "indexer_rule_kind": "Action",
+        "matching_rule": {
+            "rule": "ACTION_ANY",
+            "affected_account_id": affected_account_id,
+            "status": "SUCCESS",
+        },
+    })
+}
@@ -0,0 +1,77 @@
+use futures::channel::mpsc;
+use futures::SinkExt;
+use near_lake_primitives::block::Block;
+
+/// Credentials the Lake containers are started with. LocalStack accepts any.
+pub const FAKE_ACCESS_KEY_ID: &str = "FAKE_LOCALSTACK_KEY_ID";
+pub const FAKE_SECRET_ACCESS_KEY: &str = "FAKE_LOCALSTACK_ACCESS_KEY";
+
+/// Number of blocks buffered between the Lake framework and the consumer of [`BlockStream`].
+const BLOCK_STREAM_BUFFER: usize = 100;
+
+/// Blocks read from a Lake bucket, in height order.
+pub type BlockStream = mpsc::Receiver<Block>;
+
+/// Location of a Lake bucket in an S3-compatible storage.
+#[derive(Clone, Debug)]
+pub struct LakeS3Config {
+    /// S3 endpoint, e.g. `http://127.0.0.1:4566`.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+}
+
+impl LakeS3Config {
+    /// S3 client config with the fake Lake credentials and path-style addressing.
+    pub fn aws_s3_config(&self) -> aws_sdk_s3::Config {
+        let credentials = aws_sdk_s3::config::Credentials::new(
+            FAKE_ACCESS_KEY_ID,
+            FAKE_SECRET_ACCESS_KEY,
+            None,
+            None,
+            "nearhat",
+        );
+        aws_sdk_s3::Config::builder()
+            .behavior_version(aws_sdk_s3::config::BehaviorVersion::latest())
+            .credentials_provider(credentials)
+            .endpoint_url(&self.endpoint)
+            .region(aws_sdk_s3::config::Region::new(self.region.clone()))
+            .force_path_style(true)
+            .build()
+    }
+
+    /// Stream blocks from the bucket starting at `from_height`, waiting for new blocks once
+    /// the stream catches up.
+    ///
+    /// The Lake framework runs its own runtime, so it is driven from a dedicated thread that
+    /// stops once the stream is dropped.
+    pub fn stream_blocks(&self, from_height: u64) -> anyhow::Result<BlockStream> {
+        let lake = near_lake_framework::LakeBuilder::default()
+            .s3_config(self.aws_s3_config())
+            .s3_bucket_name(&self.bucket)
+            .s3_region_name(&self.region)
+            .start_block_height(from_height)
+            .build()?;
+
+        let (sender, receiver) = mpsc::channel(BLOCK_STREAM_BUFFER);
+        std::thread::Builder::new()
+            .name("nearhat-lake-stream".to_string())
+            .spawn(move || {
+                let result = lake.run(move |block: Block| {
+                    let mut sender = sender.clone();
+                    async move {
+                        sender.send(block).await?;
+                        Ok(())
+                    }
+                });
+                match result {
+                    Ok(()) => tracing::info!("lake block stream finished"),
+                    // Dropping the stream also ends up here, as the next send fails.
+                    Err(err) => tracing::info!(%err, "lake block stream stopped"),
+                }
+            })?;
+        tracing::info!(endpoint = self.endpoint, bucket = self.bucket, from_height, "lake block stream started");
+
+        Ok(receiver)
+    }
+}
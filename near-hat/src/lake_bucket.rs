@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use serde_json::Value;
+
+use crate::lake::LakeS3Config;
+
+/// Lake bucket access through plain (unsigned) S3 REST calls, so that it also works through
+/// the NEARHat reverse proxy, e.g. with `http://lake.nearhat`.
+///
+/// Blocks are stored as `<height padded to 12 digits>/block.json` and `.../shard_<N>.json`.
+pub struct LakeBucket {
+    http: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+}
+
+impl LakeBucket {
+    pub fn new(endpoint: &str, bucket: &str) -> LakeBucket {
+        LakeBucket {
+            http: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+        }
+    }
+
+    pub fn from_config(lake: &LakeS3Config) -> LakeBucket {
+        LakeBucket::new(&lake.endpoint, &lake.bucket)
+    }
+
+    /// Fail unless the bucket exists and can be listed anonymously.
+    pub async fn check_accessible(&self) -> anyhow::Result<()> {
+        self.list(&[("list-type", "2".to_string()), ("max-keys", "0".to_string())])
+            .await
+            .with_context(|| format!("checking lake bucket {}", self.bucket))?;
+        Ok(())
+    }
+
+    /// Heights of all stored blocks in `[from, to]`, ascending.
+    pub async fn heights(&self, from: Option<u64>, to: Option<u64>) -> anyhow::Result<Vec<u64>> {
+        let mut heights = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2".to_string()), ("delimiter", "/".to_string())];
+            if let Some(from) = from.filter(|from| *from > 0) {
+                query.push(("start-after", block_prefix(from - 1)));
+            }
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.clone()));
+            }
+            let body = self.list(&query).await?;
+
+            for prefix in xml_values(&body, "Prefix") {
+                let Ok(height) = prefix.trim_end_matches('/').parse::<u64>() else {
+                    continue;
+                };
+                // `start-after` compares keys, so the `<from - 1>/` prefix itself is listed too.
+                if from.map_or(false, |from| height < from) {
+                    continue;
+                }
+                if to.map_or(false, |to| height > to) {
+                    return Ok(heights);
+                }
+                heights.push(height);
+            }
+
+            continuation_token = xml_values(&body, "NextContinuationToken").pop();
+            if xml_values(&body, "IsTruncated").first().map(String::as_str) != Some("true")
+                || continuation_token.is_none()
+            {
+                return Ok(heights);
+            }
+        }
+    }
+
+    /// Height of the most recently uploaded block.
+    pub async fn latest_height(&self) -> anyhow::Result<Option<u64>> {
+        Ok(self.heights(None, None).await?.pop())
+    }
+
+    /// Files stored for a block, e.g. `block.json` and `shard_0.json`.
+    pub async fn files(&self, height: u64) -> anyhow::Result<Vec<String>> {
+        let prefix = format!("{}/", block_prefix(height));
+        let body = self
+            .list(&[("list-type", "2".to_string()), ("prefix", prefix.clone())])
+            .await?;
+        Ok(xml_values(&body, "Key")
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&prefix).map(str::to_string))
+            .collect())
+    }
+
+    /// Parsed `shard_<N>.json` files of a block.
+    pub async fn shards(&self, height: u64) -> anyhow::Result<Vec<Value>> {
+        let mut shards = Vec::new();
+        for file in self.files(height).await? {
+            if file.starts_with("shard_") {
+                shards.push(self.get_json(height, &file).await?);
+            }
+        }
+        Ok(shards)
+    }
+
+    pub async fn get(&self, height: u64, file: &str) -> anyhow::Result<Vec<u8>> {
+        let url = self.object_url(height, file);
+        let response = self.http.get(&url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("fetching {} failed with {}", url, response.status()));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn get_json(&self, height: u64, file: &str) -> anyhow::Result<Value> {
+        let bytes = self.get(height, file).await?;
+        serde_json::from_slice(&bytes).with_context(|| format!("parsing {}/{}", height, file))
+    }
+
+    pub async fn put(&self, height: u64, file: &str, contents: Vec<u8>) -> anyhow::Result<()> {
+        let url = self.object_url(height, file);
+        let response = self.http.put(&url).body(contents).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("uploading {} failed with {}", url, response.status()));
+        }
+        Ok(())
+    }
+
+    /// Download blocks in `[from, to]` into `dir/<height>/<file>`. Returns the number of blocks.
+    pub async fn export(&self, from: u64, to: u64, dir: &Path) -> anyhow::Result<usize> {
+        let heights = self.heights(Some(from), Some(to)).await?;
+        for &height in &heights {
+            let block_dir = dir.join(block_prefix(height));
+            std::fs::create_dir_all(&block_dir)
+                .with_context(|| format!("creating {}", block_dir.display()))?;
+            for file in self.files(height).await? {
+                let contents = self.get(height, &file).await?;
+                std::fs::write(block_dir.join(&file), contents)?;
+            }
+        }
+        tracing::info!(from, to, blocks = heights.len(), dir = %dir.display(), "lake blocks exported");
+        Ok(heights.len())
+    }
+
+    /// Upload blocks previously written by [`LakeBucket::export`]. Returns the number of blocks.
+    pub async fn import(&self, dir: &Path) -> anyhow::Result<usize> {
+        let mut blocks = 0;
+        for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let Some(height) = entry.file_name().to_str().and_then(|name| name.parse().ok()) else {
+                continue;
+            };
+            for file in std::fs::read_dir(entry.path())? {
+                let file = file?;
+                if !file.file_type()?.is_file() {
+                    continue;
+                }
+                let name = file.file_name().to_string_lossy().to_string();
+                self.put(height, &name, std::fs::read(file.path())?).await?;
+            }
+            blocks += 1;
+        }
+        tracing::info!(blocks, dir = %dir.display(), "lake blocks imported");
+        Ok(blocks)
+    }
+
+    async fn list(&self, query: &[(&str, String)]) -> anyhow::Result<String> {
+        let url = format!("{}/{}", self.endpoint, self.bucket);
+        let response = self.http.get(&url).query(query).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow!("listing {} failed with {}: {}", url, status, body));
+        }
+        Ok(body)
+    }
+
+    fn object_url(&self, height: u64, file: &str) -> String {
+        format!("{}/{}/{}/{}", self.endpoint, self.bucket, block_prefix(height), file)
+    }
+}
+
+fn block_prefix(height: u64) -> String {
+    format!("{:012}", height)
+}
+
+/// Text of every `<tag>...</tag>` element. S3 list responses are flat enough for this.
+fn xml_values(body: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    body.split(&open)
+        .skip(1)
+        .filter_map(|rest| rest.split(&close).next())
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use near_lake_primitives::block::Block;
+
+use crate::ctx::nearcore::NearcoreCtx;
+use crate::lake::LakeS3Config;
+use crate::wait::{poll_until, WAIT_TIMEOUT};
+
+#[derive(Default)]
+struct Observed {
+    latest_height: Option<u64>,
+    /// Receipt id -> height of the block it was seen in.
+    receipts: HashMap<String, u64>,
+    /// Transaction hash -> height of the block it was seen in.
+    transactions: HashMap<String, u64>,
+    /// Errors returned by the handler, with the height of the failing block.
+    errors: Vec<(u64, String)>,
+}
+
+/// Runs a user indexer handler against the local Lake bucket and records what it was fed, so
+/// that tests can drive transactions through [`NearcoreCtx`] and assert on the handler.
+///
+/// The handler keeps running until the harness is dropped.
+pub struct LakeHarness {
+    observed: Arc<Mutex<Observed>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl LakeHarness {
+    pub fn start<F, Fut>(
+        lake: &LakeS3Config,
+        from_height: u64,
+        mut handler: F,
+    ) -> anyhow::Result<LakeHarness>
+    where
+        F: FnMut(Block) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let mut blocks = lake.stream_blocks(from_height)?;
+        let observed = Arc::new(Mutex::new(Observed::default()));
+        let task_observed = observed.clone();
+        let task = tokio::spawn(async move {
+            while let Some(mut block) = blocks.next().await {
+                let height = block.block_height();
+                let receipt_ids: Vec<String> = block
+                    .receipts()
+                    .map(|receipt| receipt.receipt_id().to_string())
+                    .collect();
+                let transaction_hashes: Vec<String> = block
+                    .transactions()
+                    .map(|transaction| transaction.transaction_hash().to_string())
+                    .collect();
+
+                let result = handler(block).await;
+
+                let mut observed = task_observed.lock().unwrap();
+                observed.latest_height = Some(height);
+                for receipt_id in receipt_ids {
+                    observed.receipts.entry(receipt_id).or_insert(height);
+                }
+                for transaction_hash in transaction_hashes {
+                    observed.transactions.entry(transaction_hash).or_insert(height);
+                }
+                if let Err(err) = result {
+                    tracing::warn!(height, %err, "lake harness handler failed");
+                    observed.errors.push((height, format!("{err:#}")));
+                }
+            }
+        });
+
+        Ok(LakeHarness { observed, task })
+    }
+
+    /// Height of the last block passed to the handler.
+    pub fn latest_height(&self) -> Option<u64> {
+        self.observed.lock().unwrap().latest_height
+    }
+
+    /// Errors returned by the handler so far, with the height of the failing block.
+    pub fn handler_errors(&self) -> Vec<(u64, String)> {
+        self.observed.lock().unwrap().errors.clone()
+    }
+
+    /// Wait until the handler has processed block `height`.
+    pub async fn wait_for_block(&self, height: u64) -> anyhow::Result<()> {
+        self.wait_for_block_with_timeout(height, WAIT_TIMEOUT).await
+    }
+
+    /// Same as [`LakeHarness::wait_for_block`], failing after `timeout`.
+    pub async fn wait_for_block_with_timeout(&self, height: u64, timeout: Duration) -> anyhow::Result<()> {
+        let what = format!("lake harness to reach block {}", height);
+        poll_until(&what, timeout, || async {
+            Ok(self.latest_height().map_or(false, |latest| latest >= height))
+        })
+        .await
+    }
+
+    /// Wait until the handler has caught up with the node's current head.
+    pub async fn catch_up(&self, nearcore_ctx: &NearcoreCtx) -> anyhow::Result<u64> {
+        let head = nearcore_ctx.worker.view_block().await?.height();
+        self.wait_for_block(head).await?;
+        Ok(head)
+    }
+
+    /// Assert that the handler was fed the receipt in a block no later than `by_block`.
+    pub async fn assert_saw_receipt(&self, receipt_id: impl Display, by_block: u64) -> anyhow::Result<()> {
+        self.assert_seen("receipt", |observed| &observed.receipts, receipt_id, by_block)
+            .await
+    }
+
+    /// Assert that the handler was fed the transaction in a block no later than `by_block`.
+    pub async fn assert_saw_transaction(
+        &self,
+        transaction_hash: impl Display,
+        by_block: u64,
+    ) -> anyhow::Result<()> {
+        self.assert_seen("transaction", |observed| &observed.transactions, transaction_hash, by_block)
+            .await
+    }
+
+    async fn assert_seen(
+        &self,
+        kind: &str,
+        seen: fn(&Observed) -> &HashMap<String, u64>,
+        id: impl Display,
+        by_block: u64,
+    ) -> anyhow::Result<()> {
+        self.wait_for_block(by_block).await?;
+        let id = id.to_string();
+        match seen(&self.observed.lock().unwrap()).get(&id) {
+            Some(&height) if height <= by_block => Ok(()),
+            Some(&height) => Err(anyhow!(
+                "{} {} was seen at block {}, after block {}",
+                kind,
+                id,
+                height,
+                by_block
+            )),
+            None => Err(anyhow!("{} {} was not seen by block {}", kind, id, by_block)),
+        }
+    }
+}
+
+impl Drop for LakeHarness {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
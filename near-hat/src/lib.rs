@@ -1,17 +1,58 @@
+mod catalog;
 mod client;
+mod config;
 mod containers;
+mod contract;
 mod ctx;
+mod events;
+mod explorer_links;
+mod hasura_client;
+mod indexer_registry;
+mod lake;
+mod lake_bucket;
+mod lake_harness;
+mod relayer_client;
+mod relayer_monitor;
+mod state;
+mod tx_tracer;
 mod validator;
+mod wait;
 
+pub use catalog::StandardContract;
 pub use client::DockerClient;
+pub use config::NearHatConfig;
+pub use containers::explorer_backend::ExplorerDbConfig;
+pub use containers::lake_indexer::{LakeFinality, LakeIndexerOptions, SyncMode};
+pub use containers::lake_storage::LakeStorageKind;
+pub use containers::relayer::{RelayerConfigError, RelayerPolicy};
+pub use contract::build_contract;
+pub use ctx::explorer::ExplorerConfig;
+pub use ctx::nearcore::NearcoreCtx;
+pub use ctx::relayer::{RelayerInstance, RelayerInstanceConfig};
+pub use events::{
+    assert_event_emitted, events_from_result, events_from_trace, events_in_blocks, EmittedEvent,
+    EventFilter, Nep297Event,
+};
+pub use explorer_links::{ExplorerLinks, EXPLORER_PROXY_URL};
+pub use hasura_client::{indexer_schema, GraphQlError, HasuraClient, HasuraRole, IndexerLogCursor, IndexerLogEntry};
+pub use indexer_registry::{action_filter, IndexerRegistry};
+pub use lake::{BlockStream, LakeS3Config};
+pub use lake_bucket::LakeBucket;
+pub use lake_harness::LakeHarness;
+pub use relayer_client::{CreateAccountAtomicRequest, RelayerClient, RelayerOutcome};
+pub use relayer_monitor::{KeyUsage, RelayerMonitorConfig, RelayerReport};
+pub use state::{AccessKeyDump, AccountDump, StateDiff, StorageEntry};
+pub use tx_tracer::{ExecutionSummary, ReceiptTrace, TxTrace, TxTracer, DEFAULT_SEARCH_DEPTH};
+pub use wait::WAIT_TIMEOUT;
 
 use ctx::explorer::ExplorerCtx;
 use ctx::lake_indexer::LakeIndexerCtx;
-use ctx::nearcore::NearcoreCtx;
 use ctx::queryapi::QueryApiCtx;
 use ctx::relayer::RelayerCtx;
+use ctx::sandbox::SandboxCtx;
+use containers::lake_storage::LakeStorage;
 use serde_json::Value;
-use std::{process::{Command, Child}, rc::Rc, cell::RefCell};
+use std::{path::Path, process::{Command, Child}, rc::Rc, cell::RefCell};
 
 
 pub struct NearHat<'a> {
@@ -19,7 +60,8 @@ pub struct NearHat<'a> {
     pub lake_indexer_ctx: LakeIndexerCtx<'a>,
     pub nearcore_ctx: NearcoreCtx,
     pub relayer_ctx: RelayerCtx<'a>,
-    pub explorer_ctx: ExplorerCtx<'a>,
+    /// `None` when the explorer is disabled in [`NearHatConfig::explorer`].
+    pub explorer_ctx: Option<ExplorerCtx<'a>>,
 }
 
 pub struct NearHatEnvironment<'a> {
@@ -27,28 +69,79 @@ pub struct NearHatEnvironment<'a> {
     pub reverse_proxy_process: Child
 }
 
+/// Sandbox-only profile: a single nearcore sandbox container with NEARHat's account helpers.
+pub struct NearHatSandbox<'a> {
+    pub sandbox_ctx: SandboxCtx<'a>,
+    pub nearcore_ctx: NearcoreCtx,
+}
+
+pub struct NearHatSandboxEnvironment<'a> {
+    pub nearhat: NearHatSandbox<'a>,
+    pub reverse_proxy_process: Child
+}
+
+/// Lake-only profile: a Lake bucket, optionally pre-loaded with exported blocks, and no node.
+pub struct NearHatLake<'a> {
+    pub storage: LakeStorage<'a>,
+}
+
+pub struct NearHatLakeEnvironment<'a> {
+    pub nearhat: NearHatLake<'a>,
+    pub reverse_proxy_process: Child
+}
+
 impl<'a> NearHat<'a> {
     pub async fn new(
         docker_client: &'a DockerClient,
         network: &str,
         key_json_ref: Rc<RefCell<Value>>,
+        config: &NearHatConfig,
     ) -> anyhow::Result<NearHatEnvironment<'a>> {
-        let lake_indexer_ctx = LakeIndexerCtx::new(&docker_client, network, key_json_ref.clone()).await?;
+        let lake_indexer_ctx = LakeIndexerCtx::new(&docker_client, network, config.lake_storage, &config.lake_indexer, key_json_ref.clone()).await?;
         let nearcore_ctx = NearcoreCtx::new(&lake_indexer_ctx.worker).await?;
-        let relayer_ctx = RelayerCtx::new(docker_client, network, &nearcore_ctx).await?;
+        let standard_contracts = nearcore_ctx
+            .deploy_standard_contracts(&config.standard_contracts, key_json_ref.clone())
+            .await?;
+        let social_db = if !config.relayer_social_db {
+            None
+        } else if let Some(social_db) = standard_contracts
+            .iter()
+            .find(|contract| contract.id().as_str() == StandardContract::SocialDb.account_id())
+        {
+            Some(social_db.clone())
+        } else {
+            nearcore_ctx
+                .deploy_standard_contracts(&[StandardContract::SocialDb], key_json_ref.clone())
+                .await?
+                .pop()
+        };
+        let relayer_ctx = RelayerCtx::new(
+            docker_client,
+            network,
+            &nearcore_ctx,
+            &config.relayer,
+            social_db.as_ref().map(|contract| contract.id()),
+            &config.relayer_instances,
+        )
+        .await?;
         let queryapi_ctx = QueryApiCtx::new(
             docker_client,
             network,
             &relayer_ctx.redis.redis_address,
-            &lake_indexer_ctx.localstack.s3_address,
-            &lake_indexer_ctx.localstack.s3_bucket,
-            &lake_indexer_ctx.localstack.s3_region,
+            &relayer_ctx.redis.host_redis_connection_ipv4(),
+            lake_indexer_ctx.storage.s3_address(),
+            lake_indexer_ctx.storage.s3_bucket(),
+            lake_indexer_ctx.storage.s3_region(),
             &nearcore_ctx,
             &lake_indexer_ctx.lake_indexer.rpc_address,
             key_json_ref.clone(),
         )
         .await?;
-        let explorer_ctx = ExplorerCtx::new(docker_client, network, &lake_indexer_ctx).await?;
+        let explorer_ctx = if config.explorer.enabled {
+            Some(ExplorerCtx::new(docker_client, network, &lake_indexer_ctx, &config.explorer).await?)
+        } else {
+            None
+        };
 
         let nearhat = NearHat {
             queryapi_ctx,
@@ -66,16 +159,119 @@ impl<'a> NearHat<'a> {
         })
     }
 
+    /// The explorer context, or an error if the explorer is disabled.
+    pub fn explorer(&self) -> anyhow::Result<&ExplorerCtx<'a>> {
+        self.explorer_ctx
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("the explorer is disabled in NearHatConfig::explorer"))
+    }
+
     fn start_reverse_proxy(nearhat: &NearHat<'_>) -> std::io::Result<Child> {
+        let relayer_instance_ports: serde_json::Map<String, Value> = nearhat
+            .relayer_ctx
+            .instances
+            .iter()
+            .map(|instance| {
+                (
+                    instance.name.clone(),
+                    instance.relayer.host_relayer_port_ipv4().into(),
+                )
+            })
+            .collect();
+        let relayer_instance_ports = Value::Object(relayer_instance_ports);
+
         let mut command = Command::new("mitmdump");
 
         command.arg("--mode").arg("regular").arg("-p").arg("80").arg("-s").arg("dns.py")
             .env("NEARHAT_RPC_PORT", &nearhat.lake_indexer_ctx.lake_indexer.host_rpc_port_ipv4().to_string())
-            .env("NEARHAT_LAKE_S3_PORT", &nearhat.lake_indexer_ctx.localstack.host_port_ipv4().to_string())
+            .env("NEARHAT_LAKE_S3_PORT", &nearhat.lake_indexer_ctx.storage.host_port_ipv4().to_string())
             .env("NEARHAT_RELAYER_PORT", &nearhat.relayer_ctx.relayer.host_relayer_port_ipv4().to_string())
-            .env("NEARHAT_EXPLORER_UI_PORT", &nearhat.explorer_ctx.frontend.host_frontend_port_ipv4().to_string())
+            .env("NEARHAT_RELAYER_INSTANCE_PORTS", relayer_instance_ports.to_string())
             .env("NEARHAT_GRAPHQL_PLAYGROUND_PORT", &nearhat.queryapi_ctx.hasura_graphql.host_playground_port_ipv4().to_string())
             .stdout(std::process::Stdio::null());
+        if let Some(frontend) = nearhat.explorer_ctx.as_ref().and_then(|explorer_ctx| explorer_ctx.frontend.as_ref()) {
+            command.env("NEARHAT_EXPLORER_UI_PORT", frontend.host_frontend_port_ipv4().to_string());
+        }
+
+        return command.spawn();
+    }
+}
+
+impl<'a> NearHatSandbox<'a> {
+    pub async fn new(
+        docker_client: &'a DockerClient,
+        network: &str,
+        key_json_ref: Rc<RefCell<Value>>,
+        config: &NearHatConfig,
+    ) -> anyhow::Result<NearHatSandboxEnvironment<'a>> {
+        let sandbox_ctx = SandboxCtx::new(docker_client, network, key_json_ref.clone()).await?;
+        let nearcore_ctx = NearcoreCtx::new(&sandbox_ctx.worker).await?;
+        nearcore_ctx
+            .deploy_standard_contracts(&config.standard_contracts, key_json_ref)
+            .await?;
+
+        let nearhat = NearHatSandbox {
+            sandbox_ctx,
+            nearcore_ctx,
+        };
+
+        let reverse_proxy_process = Self::start_reverse_proxy(&nearhat)?;
+
+        Ok(NearHatSandboxEnvironment {
+            nearhat,
+            reverse_proxy_process
+        })
+    }
+
+    fn start_reverse_proxy(nearhat: &NearHatSandbox<'_>) -> std::io::Result<Child> {
+        let mut command = Command::new("mitmdump");
+
+        command.arg("--mode").arg("regular").arg("-p").arg("80").arg("-s").arg("dns.py")
+            .env("NEARHAT_RPC_PORT", &nearhat.sandbox_ctx.sandbox.host_rpc_port_ipv4().to_string())
+            .stdout(std::process::Stdio::null());
+
+        return command.spawn();
+    }
+}
+
+impl<'a> NearHatLake<'a> {
+    pub async fn new(
+        docker_client: &'a DockerClient,
+        network: &str,
+        storage_kind: LakeStorageKind,
+        fixtures_dir: Option<&Path>,
+    ) -> anyhow::Result<NearHatLakeEnvironment<'a>> {
+        let storage = LakeStorage::run(
+            docker_client,
+            network,
+            storage_kind,
+            "localnet".to_string(),
+            "us-east-1".to_string(),
+        )
+        .await?;
+        let nearhat = NearHatLake { storage };
+        if let Some(fixtures_dir) = fixtures_dir {
+            nearhat.bucket().import(fixtures_dir).await?;
+        }
+
+        let reverse_proxy_process = Self::start_reverse_proxy(&nearhat)?;
+
+        Ok(NearHatLakeEnvironment {
+            nearhat,
+            reverse_proxy_process
+        })
+    }
+
+    pub fn bucket(&self) -> LakeBucket {
+        self.storage.bucket()
+    }
+
+    fn start_reverse_proxy(nearhat: &NearHatLake<'_>) -> std::io::Result<Child> {
+        let mut command = Command::new("mitmdump");
+
+        command.arg("--mode").arg("regular").arg("-p").arg("80").arg("-s").arg("dns.py")
+            .env("NEARHAT_LAKE_S3_PORT", &nearhat.storage.host_port_ipv4().to_string())
+            .stdout(std::process::Stdio::null());
 
         return command.spawn();
     }
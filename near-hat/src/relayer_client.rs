@@ -0,0 +1,201 @@
+use anyhow::{anyhow, Context};
+use near_crypto::{InMemorySigner, Signer};
+use near_primitives::borsh::BorshSerialize;
+use near_primitives::delegate_action::{DelegateAction, NonDelegateAction, SignedDelegateAction};
+use near_primitives::transaction::{Action, FunctionCallAction};
+use near_primitives::views::{ExecutionOutcomeWithIdView, FinalExecutionStatus};
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::{Gas, NearToken};
+use near_workspaces::{Account, AccountId, Worker};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Delegate actions expire this many blocks after they are built.
+const DELEGATE_ACTION_TTL: u64 = 100;
+
+/// Request body of the relayer's `/create_account_atomic` endpoint.
+#[derive(Serialize, Debug, Clone)]
+pub struct CreateAccountAtomicRequest {
+    pub account_id: AccountId,
+    /// Gas allowance granted to the new account.
+    pub allowance: u64,
+    pub oauth_token: String,
+    pub signed_delegate_action: SignedDelegateAction,
+}
+
+/// Outcome of a transaction sent by the relayer, as returned by its relaying endpoints.
+#[derive(Deserialize, Debug, Clone)]
+pub struct RelayerOutcome {
+    pub message: String,
+    pub status: FinalExecutionStatus,
+    #[serde(rename = "Transaction Outcome")]
+    pub transaction_outcome: ExecutionOutcomeWithIdView,
+    #[serde(rename = "Receipts Outcome")]
+    pub receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
+}
+
+impl RelayerOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self.status, FinalExecutionStatus::SuccessValue(_))
+    }
+}
+
+/// Typed client for the relayer HTTP API.
+pub struct RelayerClient {
+    http: reqwest::Client,
+    relayer_address: String,
+    worker: Worker<Sandbox>,
+}
+
+impl RelayerClient {
+    pub fn new(relayer_address: &str, worker: &Worker<Sandbox>) -> RelayerClient {
+        RelayerClient {
+            http: reqwest::Client::new(),
+            relayer_address: relayer_address.trim_end_matches('/').to_string(),
+            worker: worker.clone(),
+        }
+    }
+
+    /// Build and sign a delegate action from `sender` to `receiver_id`, using the sender's
+    /// current access key nonce.
+    pub async fn sign_delegate_action(
+        &self,
+        sender: &Account,
+        receiver_id: &AccountId,
+        actions: Vec<Action>,
+    ) -> anyhow::Result<SignedDelegateAction> {
+        let public_key = sender.secret_key().public_key();
+        let access_key = self
+            .worker
+            .view_access_key(sender.id(), &public_key)
+            .await?;
+        let block = self.worker.view_block().await?;
+
+        let delegate_action = DelegateAction {
+            sender_id: sender.id().clone(),
+            receiver_id: receiver_id.clone(),
+            actions: actions
+                .into_iter()
+                .map(NonDelegateAction::try_from)
+                .collect::<Result<_, _>>()
+                .map_err(|_| anyhow!("delegate actions cannot be nested"))?,
+            nonce: access_key.nonce + 1,
+            max_block_height: block.height() + DELEGATE_ACTION_TTL,
+            public_key: public_key.to_string().parse()?,
+        };
+        let signer = InMemorySigner::from_secret_key(
+            sender.id().clone(),
+            sender.secret_key().to_string().parse()?,
+        );
+        let signature = signer.sign(delegate_action.get_nep461_hash().as_ref());
+
+        Ok(SignedDelegateAction {
+            delegate_action,
+            signature,
+        })
+    }
+
+    /// Sign a delegate action with a single function call.
+    pub async fn sign_function_call(
+        &self,
+        sender: &Account,
+        receiver_id: &AccountId,
+        method_name: &str,
+        args: Value,
+        gas: Gas,
+        deposit: NearToken,
+    ) -> anyhow::Result<SignedDelegateAction> {
+        let action = Action::FunctionCall(FunctionCallAction {
+            method_name: method_name.to_string(),
+            args: serde_json::to_vec(&args)?,
+            gas: gas.as_gas(),
+            deposit: deposit.as_yoctonear(),
+        });
+        self.sign_delegate_action(sender, receiver_id, vec![action])
+            .await
+    }
+
+    /// POST a borsh-serialized delegate action to `/relay`.
+    pub async fn relay(&self, signed_delegate_action: &SignedDelegateAction) -> anyhow::Result<RelayerOutcome> {
+        let body = signed_delegate_action.try_to_vec()?;
+        self.post("/relay", &body).await
+    }
+
+    /// POST a JSON delegate action to `/send_meta_tx`.
+    pub async fn send_meta_tx(
+        &self,
+        signed_delegate_action: &SignedDelegateAction,
+    ) -> anyhow::Result<RelayerOutcome> {
+        self.post("/send_meta_tx", signed_delegate_action).await
+    }
+
+    /// Create an account through `/create_account_atomic` and relay the attached delegate action.
+    pub async fn create_account_atomic(
+        &self,
+        request: &CreateAccountAtomicRequest,
+    ) -> anyhow::Result<RelayerOutcome> {
+        self.post("/create_account_atomic", request).await
+    }
+
+    async fn post<T: Serialize + ?Sized, R: DeserializeOwned>(&self, path: &str, body: &T) -> anyhow::Result<R> {
+        let _span = tracing::info_span!("relayer request");
+        let url = format!("{}{}", self.relayer_address, path);
+        let response = self
+            .http
+            .post(&url)
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("sending request to {url}"))?;
+        let status = response.status();
+        let text = response.text().await?;
+        tracing::info!(url, %status, "relayer responded");
+        if !status.is_success() {
+            return Err(anyhow!("relayer request to {} failed with {}: {}", url, status, text));
+        }
+
+        serde_json::from_str(&text).with_context(|| format!("unexpected response from {}: {}", url, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn outcome(status: Value) -> Value {
+        let execution_outcome = json!({
+            "proof": [],
+            "block_hash": "11111111111111111111111111111111",
+            "id": "11111111111111111111111111111111",
+            "outcome": {
+                "logs": [],
+                "receipt_ids": [],
+                "gas_burnt": 0,
+                "tokens_burnt": "0",
+                "executor_id": "relayer.test.near",
+                "status": { "SuccessValue": "" },
+                "metadata": { "version": 1, "gas_profile": null }
+            }
+        });
+        json!({
+            "message": "Relayed and sent transaction (11111111111111111111111111111111)",
+            "status": status,
+            "Transaction Outcome": execution_outcome,
+            "Receipts Outcome": [execution_outcome],
+        })
+    }
+
+    #[test]
+    fn parses_relayer_outcomes() {
+        let parsed: RelayerOutcome = serde_json::from_value(outcome(json!({ "SuccessValue": "" }))).unwrap();
+        assert!(parsed.is_success());
+        assert_eq!(parsed.receipts_outcome.len(), 1);
+        assert_eq!(parsed.transaction_outcome.outcome.executor_id.as_str(), "relayer.test.near");
+
+        let failed = json!({ "Failure": { "InvalidTxError": "Expired" } });
+        let parsed: RelayerOutcome = serde_json::from_value(outcome(failed)).unwrap();
+        assert!(!parsed.is_success());
+    }
+}
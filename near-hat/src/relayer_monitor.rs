@@ -0,0 +1,162 @@
+use std::fmt;
+use std::time::Duration;
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::{Finality, NearToken, PublicKey};
+use near_workspaces::{AccountId, Worker};
+
+/// Nonce usage of a single relayer access key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyUsage {
+    pub public_key: PublicKey,
+    /// Nonce as of the latest (optimistic) block.
+    pub nonce: u64,
+    /// Transactions signed with this key that are in optimistic but not yet in final blocks.
+    pub pending_txs: u64,
+}
+
+/// Balance and per-key nonces of a relayer account.
+#[derive(Debug, Clone)]
+pub struct RelayerReport {
+    pub account_id: AccountId,
+    pub balance: NearToken,
+    pub keys: Vec<KeyUsage>,
+}
+
+impl RelayerReport {
+    pub async fn fetch(
+        worker: &Worker<Sandbox>,
+        account_id: &AccountId,
+        public_keys: &[PublicKey],
+    ) -> anyhow::Result<RelayerReport> {
+        let balance = worker.view_account(account_id).await?.balance;
+        let mut keys = Vec::with_capacity(public_keys.len());
+        for public_key in public_keys {
+            let optimistic = worker
+                .view_access_key(account_id, public_key)
+                .finality(Finality::None)
+                .await?;
+            let r#final = worker
+                .view_access_key(account_id, public_key)
+                .finality(Finality::Final)
+                .await?;
+            keys.push(KeyUsage {
+                public_key: public_key.clone(),
+                nonce: optimistic.nonce,
+                pending_txs: optimistic.nonce.saturating_sub(r#final.nonce),
+            });
+        }
+
+        Ok(RelayerReport {
+            account_id: account_id.clone(),
+            balance,
+            keys,
+        })
+    }
+
+    pub fn pending_txs(&self) -> u64 {
+        self.keys.iter().map(|key| key.pending_txs).sum()
+    }
+}
+
+impl fmt::Display for RelayerReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} yoctoNEAR, {} pending tx(s)",
+            self.account_id,
+            self.balance.as_yoctonear(),
+            self.pending_txs()
+        )?;
+        for key in &self.keys {
+            writeln!(
+                f,
+                "  {} nonce={} pending={}",
+                key.public_key, key.nonce, key.pending_txs
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Settings for [`spawn_monitor`].
+#[derive(Debug, Clone)]
+pub struct RelayerMonitorConfig {
+    pub interval: Duration,
+    /// Top up relayer accounts from the root account once their balance drops below this.
+    /// `None` disables top-ups.
+    pub top_up_threshold: Option<NearToken>,
+    pub top_up_amount: NearToken,
+}
+
+impl Default for RelayerMonitorConfig {
+    fn default() -> Self {
+        RelayerMonitorConfig {
+            interval: Duration::from_secs(5),
+            top_up_threshold: Some(NearToken::from_near(100)),
+            top_up_amount: NearToken::from_near(500),
+        }
+    }
+}
+
+/// Transfer `amount` from the root account to `account_id` if its balance is below `threshold`.
+/// Returns the transferred amount, if any.
+pub async fn top_up(
+    worker: &Worker<Sandbox>,
+    account_id: &AccountId,
+    threshold: NearToken,
+    amount: NearToken,
+) -> anyhow::Result<Option<NearToken>> {
+    let balance = worker.view_account(account_id).await?.balance;
+    if balance >= threshold {
+        return Ok(None);
+    }
+
+    worker
+        .root_account()?
+        .transfer_near(account_id, amount)
+        .await?
+        .into_result()?;
+    tracing::info!(
+        %account_id,
+        balance = balance.as_yoctonear(),
+        amount = amount.as_yoctonear(),
+        "relayer account topped up"
+    );
+    Ok(Some(amount))
+}
+
+/// Periodically log a [`RelayerReport`] for every account and top it up when configured.
+/// The monitor runs until the returned handle is aborted.
+pub fn spawn_monitor(
+    worker: Worker<Sandbox>,
+    accounts: Vec<(AccountId, Vec<PublicKey>)>,
+    config: RelayerMonitorConfig,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(config.interval);
+        loop {
+            interval.tick().await;
+            for (account_id, public_keys) in &accounts {
+                match RelayerReport::fetch(&worker, account_id, public_keys).await {
+                    Ok(report) => tracing::info!(
+                        %account_id,
+                        balance = report.balance.as_yoctonear(),
+                        pending_txs = report.pending_txs(),
+                        nonces = ?report.keys.iter().map(|key| key.nonce).collect::<Vec<_>>(),
+                        "relayer report"
+                    ),
+                    Err(err) => tracing::warn!(%account_id, %err, "failed to fetch relayer report"),
+                }
+
+                if let Some(threshold) = config.top_up_threshold {
+                    if let Err(err) =
+                        top_up(&worker, account_id, threshold, config.top_up_amount).await
+                    {
+                        tracing::warn!(%account_id, %err, "failed to top up relayer account");
+                    }
+                }
+            }
+        }
+    })
+}
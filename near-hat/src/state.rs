@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use base64::Engine;
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::AccessKeyPermission;
+use near_workspaces::{AccountId, Worker};
+use serde::{Deserialize, Serialize};
+
+/// Snapshot of an account: balance, access keys, code hash and full contract storage.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountDump {
+    pub account_id: AccountId,
+    /// Balance in yoctoNEAR.
+    pub balance: String,
+    /// Locked balance in yoctoNEAR.
+    pub locked: String,
+    pub code_hash: String,
+    pub storage_usage: u64,
+    pub access_keys: Vec<AccessKeyDump>,
+    pub storage: Vec<StorageEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccessKeyDump {
+    pub public_key: String,
+    pub nonce: u64,
+    pub permission: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StorageEntry {
+    /// Key decoded as UTF-8, if it is valid UTF-8.
+    pub key: Option<String>,
+    pub key_base64: String,
+    /// Value decoded as UTF-8, if it is valid UTF-8.
+    pub value: Option<String>,
+    pub value_base64: String,
+}
+
+impl StorageEntry {
+    fn new(key: &[u8], value: &[u8]) -> Self {
+        let engine = base64::engine::general_purpose::STANDARD;
+        StorageEntry {
+            key: String::from_utf8(key.to_vec()).ok(),
+            key_base64: engine.encode(key),
+            value: String::from_utf8(value.to_vec()).ok(),
+            value_base64: engine.encode(value),
+        }
+    }
+
+    /// Human readable key: UTF-8 when possible, base64 otherwise.
+    pub fn display_key(&self) -> &str {
+        self.key.as_deref().unwrap_or(&self.key_base64)
+    }
+
+    fn display_value(&self) -> &str {
+        self.value.as_deref().unwrap_or(&self.value_base64)
+    }
+}
+
+impl AccountDump {
+    pub async fn fetch(worker: &Worker<Sandbox>, account_id: &AccountId) -> anyhow::Result<AccountDump> {
+        let _span = tracing::info_span!("dumping account state");
+        let details = worker.view_account(account_id).await?;
+        let access_keys = worker
+            .view_access_keys(account_id)
+            .await?
+            .into_iter()
+            .map(|info| AccessKeyDump {
+                public_key: info.public_key.to_string(),
+                nonce: info.access_key.nonce,
+                permission: match info.access_key.permission {
+                    AccessKeyPermission::FullAccess => "FullAccess".to_string(),
+                    AccessKeyPermission::FunctionCall(permission) => format!(
+                        "FunctionCall(receiver: {}, methods: [{}])",
+                        permission.receiver_id,
+                        permission.method_names.join(", ")
+                    ),
+                },
+            })
+            .collect();
+        let storage = worker
+            .view_state(account_id)
+            .await?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+            .iter()
+            .map(|(key, value)| StorageEntry::new(key, value))
+            .collect::<Vec<_>>();
+        tracing::info!(%account_id, storage_entries = storage.len(), "account state dumped");
+
+        Ok(AccountDump {
+            account_id: account_id.clone(),
+            balance: details.balance.as_yoctonear().to_string(),
+            locked: details.locked.as_yoctonear().to_string(),
+            code_hash: details.code_hash.to_string(),
+            storage_usage: details.storage_usage,
+            access_keys,
+            storage,
+        })
+    }
+
+    /// Compute what changed between `self` (before) and `after`.
+    pub fn diff(&self, after: &AccountDump) -> StateDiff {
+        let before_storage = self
+            .storage
+            .iter()
+            .map(|entry| (entry.key_base64.as_str(), entry))
+            .collect::<BTreeMap<_, _>>();
+        let after_storage = after
+            .storage
+            .iter()
+            .map(|entry| (entry.key_base64.as_str(), entry))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut diff = StateDiff {
+            account_id: Some(after.account_id.clone()),
+            ..Default::default()
+        };
+        if self.balance != after.balance {
+            diff.balance = Some((self.balance.clone(), after.balance.clone()));
+        }
+        if self.locked != after.locked {
+            diff.locked = Some((self.locked.clone(), after.locked.clone()));
+        }
+        if self.code_hash != after.code_hash {
+            diff.code_hash = Some((self.code_hash.clone(), after.code_hash.clone()));
+        }
+        diff.added_access_keys = after
+            .access_keys
+            .iter()
+            .filter(|key| !self.access_keys.iter().any(|k| k.public_key == key.public_key))
+            .cloned()
+            .collect();
+        diff.removed_access_keys = self
+            .access_keys
+            .iter()
+            .filter(|key| !after.access_keys.iter().any(|k| k.public_key == key.public_key))
+            .cloned()
+            .collect();
+        diff.changed_access_keys = self
+            .access_keys
+            .iter()
+            .filter_map(|before| {
+                after
+                    .access_keys
+                    .iter()
+                    .find(|k| k.public_key == before.public_key && *k != before)
+                    .map(|after| (before.clone(), after.clone()))
+            })
+            .collect();
+        for (key, entry) in &after_storage {
+            match before_storage.get(key) {
+                None => diff.added.push((*entry).clone()),
+                Some(before) if before.value_base64 != entry.value_base64 => {
+                    diff.changed.push(((*before).clone(), (*entry).clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, entry) in &before_storage {
+            if !after_storage.contains_key(key) {
+                diff.removed.push((*entry).clone());
+            }
+        }
+        diff
+    }
+}
+
+/// Changes between two [`AccountDump`]s. Field pairs are `(before, after)`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    pub account_id: Option<AccountId>,
+    pub balance: Option<(String, String)>,
+    pub locked: Option<(String, String)>,
+    pub code_hash: Option<(String, String)>,
+    pub added_access_keys: Vec<AccessKeyDump>,
+    pub removed_access_keys: Vec<AccessKeyDump>,
+    /// Access keys whose nonce or permission changed.
+    pub changed_access_keys: Vec<(AccessKeyDump, AccessKeyDump)>,
+    pub added: Vec<StorageEntry>,
+    pub removed: Vec<StorageEntry>,
+    pub changed: Vec<(StorageEntry, StorageEntry)>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_none()
+            && self.locked.is_none()
+            && self.code_hash.is_none()
+            && self.added_access_keys.is_empty()
+            && self.removed_access_keys.is_empty()
+            && self.changed_access_keys.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(account_id) = &self.account_id {
+            writeln!(f, "account {account_id}:")?;
+        }
+        if self.is_empty() {
+            return writeln!(f, "  no changes");
+        }
+        if let Some((before, after)) = &self.balance {
+            writeln!(f, "~ balance: {before} -> {after}")?;
+        }
+        if let Some((before, after)) = &self.locked {
+            writeln!(f, "~ locked: {before} -> {after}")?;
+        }
+        if let Some((before, after)) = &self.code_hash {
+            writeln!(f, "~ code_hash: {before} -> {after}")?;
+        }
+        for key in &self.added_access_keys {
+            writeln!(f, "+ access key {} ({})", key.public_key, key.permission)?;
+        }
+        for key in &self.removed_access_keys {
+            writeln!(f, "- access key {} ({})", key.public_key, key.permission)?;
+        }
+        for (before, after) in &self.changed_access_keys {
+            write!(f, "~ access key {}:", after.public_key)?;
+            if before.nonce != after.nonce {
+                write!(f, " nonce {} -> {}", before.nonce, after.nonce)?;
+            }
+            if before.permission != after.permission {
+                write!(f, " ({}) -> ({})", before.permission, after.permission)?;
+            }
+            writeln!(f)?;
+        }
+        for entry in &self.added {
+            writeln!(f, "+ {}: {}", entry.display_key(), entry.display_value())?;
+        }
+        for entry in &self.removed {
+            writeln!(f, "- {}: {}", entry.display_key(), entry.display_value())?;
+        }
+        for (before, after) in &self.changed {
+            writeln!(
+                f,
+                "~ {}: {} -> {}",
+                after.display_key(),
+                before.display_value(),
+                after.display_value()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access_key(public_key: &str, nonce: u64, permission: &str) -> AccessKeyDump {
+        AccessKeyDump {
+            public_key: public_key.to_string(),
+            nonce,
+            permission: permission.to_string(),
+        }
+    }
+
+    fn dump(access_keys: Vec<AccessKeyDump>, storage: &[(&str, &str)]) -> AccountDump {
+        AccountDump {
+            account_id: "alice.test.near".parse().unwrap(),
+            balance: "100".to_string(),
+            locked: "0".to_string(),
+            code_hash: "11111111111111111111111111111111".to_string(),
+            storage_usage: 182,
+            access_keys,
+            storage: storage
+                .iter()
+                .map(|(key, value)| StorageEntry::new(key.as_bytes(), value.as_bytes()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn diff_of_identical_dumps_is_empty() {
+        let before = dump(vec![access_key("ed25519:a", 1, "FullAccess")], &[("STATE", "x")]);
+        let diff = before.diff(&before.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "account alice.test.near:\n  no changes\n");
+    }
+
+    #[test]
+    fn diff_reports_account_fields() {
+        let before = dump(vec![], &[]);
+        let after = AccountDump {
+            balance: "90".to_string(),
+            code_hash: "22222222222222222222222222222222".to_string(),
+            ..before.clone()
+        };
+        let diff = before.diff(&after);
+        assert_eq!(diff.balance, Some(("100".to_string(), "90".to_string())));
+        assert_eq!(diff.locked, None);
+        assert!(diff.code_hash.is_some());
+        assert!(diff.to_string().contains("~ balance: 100 -> 90\n"));
+    }
+
+    #[test]
+    fn diff_reports_storage_changes() {
+        let before = dump(vec![], &[("kept", "1"), ("changed", "1"), ("removed", "1")]);
+        let after = dump(vec![], &[("kept", "1"), ("changed", "2"), ("added", "1")]);
+        let diff = before.diff(&after);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].display_key(), "added");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].display_key(), "removed");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].0.display_value(), "1");
+        assert_eq!(diff.changed[0].1.display_value(), "2");
+        let rendered = diff.to_string();
+        assert!(rendered.contains("+ added: 1\n"));
+        assert!(rendered.contains("- removed: 1\n"));
+        assert!(rendered.contains("~ changed: 1 -> 2\n"));
+    }
+
+    #[test]
+    fn diff_reports_access_key_changes() {
+        let before = dump(
+            vec![
+                access_key("ed25519:kept", 1, "FullAccess"),
+                access_key("ed25519:used", 1, "FullAccess"),
+                access_key("ed25519:restricted", 1, "FullAccess"),
+                access_key("ed25519:removed", 1, "FullAccess"),
+            ],
+            &[],
+        );
+        let after = dump(
+            vec![
+                access_key("ed25519:kept", 1, "FullAccess"),
+                access_key("ed25519:used", 5, "FullAccess"),
+                access_key("ed25519:restricted", 1, "FunctionCall(receiver: app.near, methods: [])"),
+                access_key("ed25519:added", 0, "FullAccess"),
+            ],
+            &[],
+        );
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_access_keys, vec![access_key("ed25519:added", 0, "FullAccess")]);
+        assert_eq!(diff.removed_access_keys, vec![access_key("ed25519:removed", 1, "FullAccess")]);
+        let changed = diff
+            .changed_access_keys
+            .iter()
+            .map(|(before, after)| (before.public_key.as_str(), before.nonce, after.nonce))
+            .collect::<Vec<_>>();
+        assert_eq!(changed, vec![("ed25519:used", 1, 5), ("ed25519:restricted", 1, 1)]);
+
+        let rendered = diff.to_string();
+        assert!(rendered.contains("~ access key ed25519:used: nonce 1 -> 5\n"));
+        assert!(rendered.contains(
+            "~ access key ed25519:restricted: (FullAccess) -> (FunctionCall(receiver: app.near, methods: []))\n"
+        ));
+        assert!(!rendered.contains("ed25519:kept"));
+    }
+}
@@ -0,0 +1,470 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use anyhow::anyhow;
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::lake_bucket::LakeBucket;
+
+/// How many blocks below the latest one are searched for a transaction by default.
+pub const DEFAULT_SEARCH_DEPTH: u64 = 1000;
+
+/// Result of executing a transaction or a receipt.
+#[derive(Serialize, Debug, Clone)]
+pub struct ExecutionSummary {
+    pub executor_id: String,
+    pub gas_burnt: u64,
+    pub tokens_burnt: String,
+    pub logs: Vec<String>,
+    /// `SuccessValue`, `SuccessReceiptId`, `Failure` or `Unknown`, as returned by the RPC.
+    pub status: Value,
+}
+
+impl ExecutionSummary {
+    /// Parse the `outcome` of a Lake `execution_outcome`.
+    fn from_outcome(outcome: &Value) -> ExecutionSummary {
+        ExecutionSummary {
+            executor_id: outcome["executor_id"].as_str().unwrap_or_default().to_string(),
+            gas_burnt: outcome["gas_burnt"].as_u64().unwrap_or_default(),
+            tokens_burnt: outcome["tokens_burnt"].as_str().unwrap_or_default().to_string(),
+            logs: outcome["logs"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|log| log.as_str().map(str::to_string))
+                .collect(),
+            status: outcome["status"].clone(),
+        }
+    }
+
+    pub fn failure(&self) -> Option<&Value> {
+        self.status.get("Failure")
+    }
+}
+
+/// An executed receipt and the receipts it produced.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReceiptTrace {
+    pub receipt_id: String,
+    pub predecessor_id: String,
+    pub receiver_id: String,
+    /// Gas and deposit refunds are issued by the `system` account.
+    pub is_refund: bool,
+    pub actions: Vec<Value>,
+    /// Height of the block the receipt was executed in.
+    pub block_height: u64,
+    pub outcome: ExecutionSummary,
+    pub children: Vec<ReceiptTrace>,
+}
+
+/// A transaction with its full receipt tree, built from Lake shard data.
+#[derive(Serialize, Debug, Clone)]
+pub struct TxTrace {
+    pub transaction_hash: String,
+    pub signer_id: String,
+    pub receiver_id: String,
+    /// Height of the block that included the transaction.
+    pub block_height: u64,
+    pub actions: Vec<Value>,
+    pub outcome: ExecutionSummary,
+    pub receipts: Vec<ReceiptTrace>,
+    /// Receipts that were produced but are not executed in the bucket yet.
+    pub pending_receipts: Vec<String>,
+}
+
+impl TxTrace {
+    /// Every executed receipt, depth first.
+    pub fn all_receipts(&self) -> Vec<&ReceiptTrace> {
+        fn collect<'a>(receipts: &'a [ReceiptTrace], all: &mut Vec<&'a ReceiptTrace>) {
+            for receipt in receipts {
+                all.push(receipt);
+                collect(&receipt.children, all);
+            }
+        }
+        let mut all = Vec::new();
+        collect(&self.receipts, &mut all);
+        all
+    }
+
+    /// Gas burnt by the transaction and all of its receipts.
+    pub fn total_gas_burnt(&self) -> u64 {
+        self.outcome.gas_burnt
+            + self
+                .all_receipts()
+                .iter()
+                .map(|receipt| receipt.outcome.gas_burnt)
+                .sum::<u64>()
+    }
+
+    /// Failed receipts with their failure.
+    pub fn failures(&self) -> Vec<(&str, &Value)> {
+        self.all_receipts()
+            .into_iter()
+            .filter_map(|receipt| {
+                receipt
+                    .outcome
+                    .failure()
+                    .map(|failure| (receipt.receipt_id.as_str(), failure))
+            })
+            .collect()
+    }
+
+    /// Whether all receipts of the transaction have been executed.
+    pub fn is_complete(&self) -> bool {
+        self.pending_receipts.is_empty()
+    }
+}
+
+/// Builds [`TxTrace`]s by walking the shard files of a Lake bucket.
+pub struct TxTracer {
+    bucket: LakeBucket,
+    search_depth: u64,
+}
+
+impl TxTracer {
+    pub fn new(bucket: LakeBucket) -> TxTracer {
+        TxTracer {
+            bucket,
+            search_depth: DEFAULT_SEARCH_DEPTH,
+        }
+    }
+
+    /// Number of blocks below the latest one to search when no start height is given.
+    pub fn search_depth(mut self, search_depth: u64) -> TxTracer {
+        self.search_depth = search_depth;
+        self
+    }
+
+    /// Trace a transaction. It is searched for from `from_height` upwards if given, otherwise
+    /// downwards from the latest block in the bucket.
+    pub async fn trace(&self, transaction_hash: &str, from_height: Option<u64>) -> anyhow::Result<TxTrace> {
+        // A single listing serves both the transaction search and the receipt scan.
+        let heights = self.bucket.heights(from_height, None).await?;
+        let &latest = heights
+            .last()
+            .ok_or_else(|| anyhow!("lake bucket has no blocks to search"))?;
+        let search_heights: Vec<u64> = match from_height {
+            Some(_) => heights.clone(),
+            None => {
+                let lowest = latest.saturating_sub(self.search_depth);
+                heights.iter().rev().copied().take_while(|height| *height >= lowest).collect()
+            }
+        };
+        let (block_height, transaction) = self
+            .find_transaction(transaction_hash, &search_heights)
+            .await?;
+
+        let mut executed = HashMap::new();
+        let mut pending: HashSet<String> =
+            receipt_ids(&transaction["outcome"]["execution_outcome"]["outcome"]).collect();
+        // Local receipts are executed in the same block as the transaction.
+        for &height in heights.iter().filter(|height| **height >= block_height) {
+            if pending.is_empty() {
+                break;
+            }
+            for shard in self.bucket.shards(height).await? {
+                collect_receipts(&shard, height, &mut pending, &mut executed);
+            }
+        }
+
+        let trace = build_trace(transaction_hash, block_height, &transaction, &executed, pending);
+        tracing::info!(
+            transaction_hash,
+            block_height,
+            receipts = trace.all_receipts().len(),
+            complete = trace.is_complete(),
+            "transaction traced"
+        );
+        Ok(trace)
+    }
+
+    /// Search the blocks at `heights`, in order, for the transaction.
+    async fn find_transaction(&self, transaction_hash: &str, heights: &[u64]) -> anyhow::Result<(u64, Value)> {
+        for &height in heights {
+            for shard in self.bucket.shards(height).await? {
+                if let Some(transaction) = find_in_shard(&shard, transaction_hash) {
+                    return Ok((height, transaction.clone()));
+                }
+            }
+        }
+        Err(anyhow!(
+            "transaction {} not found in the lake bucket",
+            transaction_hash
+        ))
+    }
+}
+
+/// Receipt id -> (height, receipt, outcome) of an executed receipt.
+type ExecutedReceipts = HashMap<String, (u64, Value, Value)>;
+
+fn find_in_shard<'a>(shard: &'a Value, transaction_hash: &str) -> Option<&'a Value> {
+    shard["chunk"]["transactions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|transaction| transaction["transaction"]["hash"] == transaction_hash)
+}
+
+/// Move receipts of `pending` that `shard` executed into `executed`, and the receipts they
+/// produced into `pending`.
+fn collect_receipts(shard: &Value, height: u64, pending: &mut HashSet<String>, executed: &mut ExecutedReceipts) {
+    for receipt_outcome in shard["receipt_execution_outcomes"].as_array().into_iter().flatten() {
+        let execution_outcome = &receipt_outcome["execution_outcome"];
+        let Some(id) = execution_outcome["id"].as_str() else {
+            continue;
+        };
+        if !pending.remove(id) {
+            continue;
+        }
+        pending.extend(receipt_ids(&execution_outcome["outcome"]));
+        executed.insert(
+            id.to_string(),
+            (
+                height,
+                receipt_outcome["receipt"].clone(),
+                execution_outcome["outcome"].clone(),
+            ),
+        );
+    }
+}
+
+fn build_trace(
+    transaction_hash: &str,
+    block_height: u64,
+    transaction: &Value,
+    executed: &ExecutedReceipts,
+    pending: HashSet<String>,
+) -> TxTrace {
+    let outcome = &transaction["outcome"]["execution_outcome"]["outcome"];
+    let mut pending_receipts: Vec<String> = pending.into_iter().collect();
+    pending_receipts.sort();
+    TxTrace {
+        transaction_hash: transaction_hash.to_string(),
+        signer_id: json_string(&transaction["transaction"]["signer_id"]),
+        receiver_id: json_string(&transaction["transaction"]["receiver_id"]),
+        block_height,
+        actions: json_array(&transaction["transaction"]["actions"]),
+        outcome: ExecutionSummary::from_outcome(outcome),
+        receipts: receipt_ids(outcome)
+            .filter_map(|id| build_receipt(&id, executed))
+            .collect(),
+        pending_receipts,
+    }
+}
+
+fn build_receipt(id: &str, executed: &ExecutedReceipts) -> Option<ReceiptTrace> {
+    let (block_height, receipt, outcome) = executed.get(id)?;
+    let predecessor_id = json_string(&receipt["predecessor_id"]);
+    Some(ReceiptTrace {
+        receipt_id: id.to_string(),
+        is_refund: predecessor_id == "system",
+        predecessor_id,
+        receiver_id: json_string(&receipt["receiver_id"]),
+        actions: json_array(&receipt["receipt"]["Action"]["actions"]),
+        block_height: *block_height,
+        outcome: ExecutionSummary::from_outcome(outcome),
+        children: receipt_ids(outcome)
+            .filter_map(|child| build_receipt(&child, executed))
+            .collect(),
+    })
+}
+
+fn receipt_ids(outcome: &Value) -> impl Iterator<Item = String> + '_ {
+    outcome["receipt_ids"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_str().map(str::to_string))
+}
+
+fn json_string(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+fn json_array(value: &Value) -> Vec<Value> {
+    value.as_array().cloned().unwrap_or_default()
+}
+
+/// Short form of an action, e.g. `FunctionCall(ft_transfer)` or `Transfer(1000)`.
+fn describe_action(action: &Value) -> String {
+    match action {
+        Value::String(name) => name.clone(),
+        Value::Object(object) => match object.iter().next() {
+            Some((name, args)) if name == "FunctionCall" => {
+                format!("FunctionCall({})", args["method_name"].as_str().unwrap_or_default())
+            }
+            Some((name, args)) if name == "Transfer" => {
+                format!("Transfer({})", args["deposit"].as_str().unwrap_or_default())
+            }
+            Some((name, _)) => name.clone(),
+            None => action.to_string(),
+        },
+        _ => action.to_string(),
+    }
+}
+
+fn describe_status(status: &Value) -> String {
+    if let Some(value) = status.get("SuccessValue").and_then(Value::as_str) {
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        return match decoded {
+            Some(decoded) if !decoded.is_empty() => format!("SuccessValue({decoded})"),
+            _ => "SuccessValue".to_string(),
+        };
+    }
+    if let Some(receipt_id) = status.get("SuccessReceiptId").and_then(Value::as_str) {
+        return format!("SuccessReceiptId({receipt_id})");
+    }
+    if let Some(failure) = status.get("Failure") {
+        return format!("Failure: {failure}");
+    }
+    status.to_string()
+}
+
+fn write_details(
+    f: &mut fmt::Formatter<'_>,
+    prefix: &str,
+    actions: &[Value],
+    outcome: &ExecutionSummary,
+) -> fmt::Result {
+    if !actions.is_empty() {
+        let actions: Vec<String> = actions.iter().map(describe_action).collect();
+        writeln!(f, "{prefix}actions: {}", actions.join(", "))?;
+    }
+    for log in &outcome.logs {
+        writeln!(f, "{prefix}log: {log}")?;
+    }
+    writeln!(
+        f,
+        "{prefix}gas burnt: {:.2} Tgas, {}",
+        outcome.gas_burnt as f64 / 1e12,
+        describe_status(&outcome.status)
+    )
+}
+
+fn write_receipt(f: &mut fmt::Formatter<'_>, receipt: &ReceiptTrace, prefix: &str, last: bool) -> fmt::Result {
+    let kind = if receipt.is_refund { "refund" } else { "receipt" };
+    writeln!(
+        f,
+        "{prefix}{}{kind} {} {} -> {} [block {}]",
+        if last { "└── " } else { "├── " },
+        receipt.receipt_id,
+        receipt.predecessor_id,
+        receipt.receiver_id,
+        receipt.block_height
+    )?;
+    let child_prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+    let details_prefix = format!(
+        "{child_prefix}{}",
+        if receipt.children.is_empty() { "  " } else { "│ " }
+    );
+    write_details(f, &details_prefix, &receipt.actions, &receipt.outcome)?;
+    for (i, child) in receipt.children.iter().enumerate() {
+        write_receipt(f, child, &child_prefix, i + 1 == receipt.children.len())?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for TxTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "transaction {} {} -> {} [block {}]",
+            self.transaction_hash, self.signer_id, self.receiver_id, self.block_height
+        )?;
+        let details_prefix = if self.receipts.is_empty() { "  " } else { "│ " };
+        write_details(f, details_prefix, &self.actions, &self.outcome)?;
+        for (i, receipt) in self.receipts.iter().enumerate() {
+            write_receipt(f, receipt, "", i + 1 == self.receipts.len())?;
+        }
+        writeln!(f, "total gas burnt: {:.2} Tgas", self.total_gas_burnt() as f64 / 1e12)?;
+        if !self.pending_receipts.is_empty() {
+            writeln!(f, "pending receipts: {}", self.pending_receipts.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shards of a traced `ft_transfer`, laid out like a `lake export`.
+    fn fixture_shards() -> Vec<(u64, Value)> {
+        [
+            (100, include_str!("../testdata/tx_trace/000000000100/shard_0.json")),
+            (101, include_str!("../testdata/tx_trace/000000000101/shard_0.json")),
+            (102, include_str!("../testdata/tx_trace/000000000102/shard_0.json")),
+        ]
+        .into_iter()
+        .map(|(height, json)| (height, serde_json::from_str(json).unwrap()))
+        .collect()
+    }
+
+    fn trace_fixture(shards: &[(u64, Value)]) -> TxTrace {
+        let (block_height, transaction) = shards
+            .iter()
+            .find_map(|(height, shard)| find_in_shard(shard, "TxHash1").map(|tx| (*height, tx)))
+            .unwrap();
+        let mut executed = HashMap::new();
+        let mut pending: HashSet<String> =
+            receipt_ids(&transaction["outcome"]["execution_outcome"]["outcome"]).collect();
+        for (height, shard) in shards.iter().filter(|(height, _)| *height >= block_height) {
+            collect_receipts(shard, *height, &mut pending, &mut executed);
+        }
+        build_trace("TxHash1", block_height, transaction, &executed, pending)
+    }
+
+    #[test]
+    fn builds_receipt_tree_from_shards() {
+        let trace = trace_fixture(&fixture_shards());
+        assert_eq!(trace.block_height, 100);
+        assert_eq!(trace.signer_id, "alice.near");
+        assert_eq!(trace.receiver_id, "ft.near");
+        assert!(trace.is_complete());
+        assert!(trace.failures().is_empty());
+        assert_eq!(trace.total_gas_burnt(), 5_651_182_562_500);
+
+        let receipts = trace.all_receipts();
+        let ids: Vec<(&str, u64, bool)> = receipts
+            .iter()
+            .map(|r| (r.receipt_id.as_str(), r.block_height, r.is_refund))
+            .collect();
+        assert_eq!(ids, vec![("R1", 101, false), ("R2", 102, true)]);
+        assert_eq!(receipts[0].children.len(), 1);
+        assert_eq!(receipts[0].outcome.logs.len(), 1);
+    }
+
+    #[test]
+    fn reports_receipts_missing_from_the_bucket_as_pending() {
+        let mut shards = fixture_shards();
+        shards.pop();
+        let trace = trace_fixture(&shards);
+        assert!(!trace.is_complete());
+        assert_eq!(trace.pending_receipts, vec!["R2".to_string()]);
+        assert_eq!(trace.all_receipts().len(), 1);
+    }
+
+    #[test]
+    fn renders_receipt_tree() {
+        let rendered = trace_fixture(&fixture_shards()).to_string();
+        let expected = "\
+transaction TxHash1 alice.near -> ft.near [block 100]
+│ actions: FunctionCall(ft_transfer)
+│ gas burnt: 2.43 Tgas, SuccessReceiptId(R1)
+└── receipt R1 alice.near -> ft.near [block 101]
+    │ actions: FunctionCall(ft_transfer)
+    │ log: EVENT_JSON:{\"standard\":\"nep141\",\"version\":\"1.0.0\",\"event\":\"ft_transfer\",\"data\":[{\"old_owner_id\":\"alice.near\",\"new_owner_id\":\"bob.near\",\"amount\":\"100\"}]}
+    │ gas burnt: 3.00 Tgas, SuccessValue
+    └── refund R2 system -> alice.near [block 102]
+          actions: Transfer(1000)
+          gas burnt: 0.22 Tgas, SuccessValue
+total gas burnt: 5.65 Tgas
+";
+        assert_eq!(rendered, expected);
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// Default timeout of the `wait_for_*` helpers. Each has a `_with_timeout` variant.
+pub const WAIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often [`poll_until`] re-checks its condition.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Error returned by [`poll_until`] when its condition did not hold in time, so callers can
+/// tell a timeout apart from errors returned by the condition itself.
+#[derive(Debug)]
+pub struct TimedOut {
+    what: String,
+    timeout: Duration,
+}
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?} waiting for {}", self.timeout, self.what)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Re-check `condition` until it returns `true`, failing with [`TimedOut`] after `timeout`.
+/// `what` describes the awaited condition in the timeout error. Errors returned by `condition`
+/// are passed through.
+pub async fn poll_until<F, Fut>(what: &str, timeout: Duration, mut condition: F) -> anyhow::Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<bool>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if condition().await? {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(TimedOut {
+                what: what.to_string(),
+                timeout,
+            }
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -2,6 +2,33 @@ import { KeyPair, utils } from 'near-api-js';
 import { readFileSync, writeFileSync } from 'fs';
 import { Contract } from 'near-api-js';
 
+export const EXPLORER_URL = 'http://explorer.nearhat';
+
+export function explorerTxUrl(hash) {
+    return `${EXPLORER_URL}/transactions/${hash}`;
+}
+
+export function explorerAccountUrl(accountId) {
+    return `${EXPLORER_URL}/accounts/${accountId}`;
+}
+
+export function explorerBlockUrl(height) {
+    return `${EXPLORER_URL}/blocks/${height}`;
+}
+
+// Await a transaction, printing its explorer link if it fails.
+export async function withExplorerLink(transaction) {
+    try {
+        return await transaction;
+    } catch (error) {
+        const hash = error.transaction_outcome?.id;
+        if (hash) {
+            console.error(`Transaction failed, see ${explorerTxUrl(hash)}`);
+        }
+        throw error;
+    }
+}
+
 export async function registerIndexer(indexerName, codePath, schemaPath, affectedAccountId, near) {
     const queryApiAccount = await near.account("dev-queryapi.test.near");
     const indexerRegistry = new Contract(queryApiAccount, 'dev-queryapi.test.near', {
@@ -11,7 +38,7 @@ export async function registerIndexer(indexerName, codePath, schemaPath, affecte
     const code = readFileSync(codePath).toString();
     const schema = readFileSync(schemaPath).toString();
 
-    await indexerRegistry.account.functionCall({
+    await withExplorerLink(indexerRegistry.account.functionCall({
         contractId: indexerRegistry.contractId,
         methodName: "register_indexer_function",
         args: {
@@ -20,7 +47,7 @@ export async function registerIndexer(indexerName, codePath, schemaPath, affecte
             "schema": schema,
             "filter_json": `{\"indexer_rule_kind\":\"Action\",\"matching_rule\":{\"rule\":\"ACTION_ANY\",\"affected_account_id\":\"${affectedAccountId}\",\"status\":\"SUCCESS\"}}`
         },
-    });
+    }));
     await new Promise(resolve => setTimeout(resolve, 1000));
 }
 